@@ -1,3 +1,71 @@
+use std::path::PathBuf;
+
+use tauri::Manager;
+
+/// Name of the file the frontend's session is kept in, inside the app data directory.
+const SESSION_FILE: &str = "session.json";
+/// Directory of session recordings inside the app data directory, without a downloads directory.
+const RECORDINGS_DIR: &str = "recordings";
+
+fn session_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+  app
+    .path()
+    .app_data_dir()
+    .map(|dir| dir.join(SESSION_FILE))
+    .map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+fn load_session(app: tauri::AppHandle) -> Result<Option<String>, String> {
+  match std::fs::read_to_string(session_path(&app)?) {
+    Ok(session) => Ok(Some(session)),
+    Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+    Err(err) => Err(err.to_string()),
+  }
+}
+
+#[tauri::command]
+fn save_session(app: tauri::AppHandle, session: String) -> Result<(), String> {
+  let path = session_path(&app)?;
+  if let Some(parent) = path.parent() {
+    std::fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+  }
+  std::fs::write(path, session).map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+fn clear_session(app: tauri::AppHandle) -> Result<(), String> {
+  match std::fs::remove_file(session_path(&app)?) {
+    Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.to_string()),
+    _ => Ok(()),
+  }
+}
+
+/// Write a session recording to the downloads directory, or the app data directory when
+/// there is none, returning where it went.
+#[tauri::command]
+fn save_recording(app: tauri::AppHandle, name: String, contents: String) -> Result<String, String> {
+  // Only a file name, so the frontend can't write anywhere else
+  let name = std::path::Path::new(&name)
+    .file_name()
+    .ok_or_else(|| format!("Invalid recording name: '{name}'"))?
+    .to_owned();
+  let dir = app
+    .path()
+    .download_dir()
+    .or_else(|_| {
+      app
+        .path()
+        .app_data_dir()
+        .map(|dir| dir.join(RECORDINGS_DIR))
+    })
+    .map_err(|err| err.to_string())?;
+  std::fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+  let path = dir.join(name);
+  std::fs::write(&path, contents).map_err(|err| err.to_string())?;
+  Ok(path.display().to_string())
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
   tauri::Builder::default()
@@ -11,6 +79,12 @@ pub fn run() {
       }
       Ok(())
     })
+    .invoke_handler(tauri::generate_handler![
+      load_session,
+      save_session,
+      clear_session,
+      save_recording
+    ])
     .run(tauri::generate_context!())
     .expect("error while running tauri application");
 }
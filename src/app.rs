@@ -1,35 +1,123 @@
-use crate::utils::{Action, Result};
+use crate::utils::auth::{self, AuthConfig};
+use crate::utils::session::{self, Session, SessionStore};
+use crate::utils::{
+    browser_window, cell_position, go_back, go_forward, parse_page_from_url, push_page,
+    replace_page, save_recording, selected_text, Action, BackendSelection, BackendType, Command,
+    FocusRing, KeyResult, Keymap, Navigation, Recorder, Result, Surface, Tasks, Theme,
+};
 use derive_deref::{Deref, DerefMut};
-use ratatui::layout::Rect;
 use ratzilla::event::KeyCode;
 use ratzilla::event::KeyEvent;
+use ratzilla::event::{MouseButton, MouseEventKind};
+use ratzilla::ratatui::layout::Rect;
 use ratzilla::ratatui::Frame;
 use ratzilla::utils;
-use std::collections::HashMap;
-use tachyonfx::Effect;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
 use tokio::sync::mpsc::UnboundedReceiver;
 use tokio::sync::mpsc::UnboundedSender;
 
-use crate::pages::components::Clip;
+use crate::pages::components::copy_to_clipboard;
+use crate::pages::components::CommandLine;
+use crate::pages::components::Help;
 use crate::pages::components::Message;
+use crate::pages::components::Palette;
+use crate::pages::components::Toast;
+use crate::pages::components::{Field, Modal, Popup, Severity};
 use crate::pages::notfound::NotFound;
 use crate::pages::Component;
 use crate::pages::Login;
+use crate::pages::Verify;
 use crate::APP_NAME;
 
+/// Upper bound of actions dispatched per frame, guarding against components that keep
+/// answering each other's actions.
+pub const MAX_ACTIONS_PER_FRAME: usize = 256;
+
+/// Name of the form asking where to save a recording.
+const SAVE_RECORDING_FORM: &str = "save-recording";
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum Page {
     #[default]
     Login,
+    Verify,
     Home,
     Settings,
+    /// Not a page of its own, opens the help overlay over the current page
     Help,
 }
 
-enum InputMode {
-    Normal,
-    Other,
+impl Page {
+    pub const ALL: [Page; 5] = [
+        Page::Login,
+        Page::Verify,
+        Page::Home,
+        Page::Settings,
+        Page::Help,
+    ];
+
+    /// Page shown after logging in when no other page was requested.
+    pub const LANDING: Page = Page::Settings;
+
+    /// Whether the page can only be shown with an authenticated session.
+    pub fn requires_auth(&self) -> bool {
+        match self {
+            Page::Login | Page::Verify | Page::Help => false,
+            Page::Home | Page::Settings => true,
+        }
+    }
+
+    /// Whether the page is part of logging in, which keeps the page to return to afterwards.
+    pub fn is_login(&self) -> bool {
+        matches!(self, Page::Login | Page::Verify)
+    }
+
+    /// Get the string representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Page::Login => "login",
+            Page::Verify => "verify",
+            Page::Home => "home",
+            Page::Settings => "settings",
+            Page::Help => "help",
+        }
+    }
+
+    /// URL fragment the page is reachable under, e.g. `#/settings`.
+    pub fn path(&self) -> String {
+        format!("#/{}", self.as_str())
+    }
 }
+
+impl TryFrom<String> for Page {
+    type Error = String;
+
+    fn try_from(s: String) -> std::result::Result<Self, Self::Error> {
+        match s
+            .trim_start_matches('#')
+            .trim_matches('/')
+            .to_lowercase()
+            .as_str()
+        {
+            "login" => Ok(Page::Login),
+            "verify" => Ok(Page::Verify),
+            "home" => Ok(Page::Home),
+            "settings" => Ok(Page::Settings),
+            "help" => Ok(Page::Help),
+            _ => Err(format!(
+                "Invalid page: '{s}'. Valid options are: login, verify, home, settings, help"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Page {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
 pub struct View(Box<dyn Component>);
 
 #[derive(Deref, DerefMut)]
@@ -42,35 +130,168 @@ pub struct UiComponents(Vec<View>);
 pub struct App {
     // Tx sender
     tx: Option<UnboundedSender<Action>>,
-    /// Current input mode
-    input_mode: InputMode,
     // Current application mode/page
     current_mode: Page,
     // Page/view currently being displayed
     pub pages: Pages,
     // Components
     pub components: UiComponents,
+    // Authentication API location
+    auth: AuthConfig,
+    // Email the current login code was sent to
+    email: Option<String>,
+    // Session of the logged in user
+    session: Option<Session>,
+    // When the session expires, read from its token once as it is checked every frame
+    expiry: Option<u64>,
+    // Where the session is persisted
+    store: SessionStore,
+    // Protected page the user asked for before being sent to log in
+    redirect: Option<Page>,
+    // Pages visited before and after the current one
+    history: Navigation,
+    // Whether the pages received `on_mount` and may get lifecycle hooks
+    mounted: bool,
+    // Terminal area the pages were last resized to
+    area: Option<Rect>,
+    // Requests and other background work
+    tasks: Tasks,
+    // Key bindings for the pages and the whole app
+    keymap: Keymap,
+    // Colors the pages are drawn on
+    theme: Theme,
+    // Widgets of the current page that can take focus
+    focus: FocusRing,
+    // Backend the terminal is drawn with, and how it was picked
+    backend: BackendSelection,
+    // Backend to switch to once the current frame is drawn
+    backend_request: Option<BackendType>,
+    // Whether to start or stop recording once the current frame is drawn
+    recording_request: bool,
+    // Stopped recording waiting for the user to name it
+    unsaved_recording: Option<Recorder>,
 }
 
 impl App {
     pub fn new() -> Self {
-        let input = Message::new();
-        let clip = Clip::new();
-        let login = Login::new();
-        Self {
+        let keymap = Keymap::load();
+        let input = Message::new().edit_keys(keymap.keys_for(Page::Settings, Command::Edit));
+        let login = Login::new().edit_keys(keymap.keys_for(Page::Login, Command::Edit));
+        let verify = Verify::new();
+        let store = SessionStore::detect();
+        let session = Self::load_session(&store);
+        let expiry = session
+            .as_ref()
+            .and_then(|session| session.expiry().ok().flatten());
+        // Start on the page from the URL, going through the guard like any navigation
+        let initial = parse_page_from_url(match session {
+            Some(_) => Page::LANDING,
+            None => Page::default(),
+        });
+        let mut app = Self {
             tx: None,
-            components: UiComponents(Vec::new()),
-            input_mode: InputMode::Normal,
+            // Drawn in order, so later components are on top
+            components: UiComponents(vec![
+                View(Box::new(Toast::new())),
+                View(Box::new(Help::new())),
+                View(Box::new(Palette::new())),
+                View(Box::new(CommandLine::new())),
+                View(Box::new(Popup::new())),
+            ]),
             current_mode: Page::default(),
             pages: Pages(HashMap::from([
                 (Page::Login, View(Box::new(login))),
+                (Page::Verify, View(Box::new(verify))),
                 (Page::Settings, View(Box::new(input))),
-                (Page::Help, View(Box::new(clip))),
             ])),
+            auth: AuthConfig::default(),
+            email: None,
+            session,
+            expiry,
+            store,
+            redirect: None,
+            history: Navigation::default(),
+            mounted: false,
+            area: None,
+            tasks: Tasks::new(),
+            keymap,
+            theme: Theme::default(),
+            focus: FocusRing::default(),
+            backend: BackendSelection::direct(BackendType::default()),
+            backend_request: None,
+            recording_request: false,
+            unsaved_recording: None,
+        };
+        app.show(initial);
+        app
+    }
+
+    /// Read a still valid session from the store, dropping it if it has expired.
+    fn load_session(store: &SessionStore) -> Option<Session> {
+        let session = match store.load() {
+            Ok(session) => session?,
+            Err(err) => {
+                log::error!("Failed to restore session: {}", err);
+                return None;
+            }
+        };
+        match session.check() {
+            Ok(()) => Some(session),
+            Err(err) => {
+                log::info!("Discarding stored session: {}", err);
+                store.clear().ok();
+                None
+            }
+        }
+    }
+
+    /// Restore the session from stores that can only be read asynchronously.
+    ///
+    /// The app starts on the login page and moves on once `Action::StartSession` arrives.
+    pub fn restore_session(&mut self) {
+        if self.store.is_async() {
+            self.tasks.spawn(None, self.store.clone().restore());
+        }
+    }
+
+    /// Record the backend the terminal was built with, telling the user if it isn't the
+    /// one they asked for.
+    pub fn set_backend(&mut self, selection: BackendSelection) {
+        if let Some(notice) = selection.notice() {
+            self.send(Action::Notify(
+                Severity::Warning,
+                "Backend fallback".to_string(),
+                notice,
+            ));
+        }
+        self.backend = selection;
+    }
+
+    /// Apply the requests that change the terminal itself, once the current frame is drawn.
+    ///
+    /// The terminal can't be rebuilt or redrawn while it is drawing the app, so whoever drives
+    /// the frames calls this between two of them.
+    pub fn between_frames(&mut self, surface: &mut Surface) {
+        if std::mem::take(&mut self.recording_request) {
+            self.toggle_recording(surface);
+        }
+        if let Some(backend_type) = self.backend_request.take() {
+            match surface.switch(backend_type) {
+                Ok(selection) => self.set_backend(selection),
+                Err(err) => log::error!("No backend left to draw with: {}", err),
+            }
         }
     }
 
+    /// Use a different authentication API than the compiled-in default.
+    pub fn with_auth_config(mut self, auth: AuthConfig) -> Self {
+        self.auth = auth;
+        self
+    }
+
     pub fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
+        self.tx = Some(tx.clone());
+        self.tasks.register_action_handler(tx.clone());
         for (_, page) in self.pages.iter_mut() {
             page.0.register_action_handler(tx.clone())?;
         }
@@ -79,115 +300,784 @@ impl App {
         }
         Ok(())
     }
+
+    /// Run `on_mount` on every page and component, then `on_enter` on the current page.
+    ///
+    /// Call once after [`App::register_action_handler`].
+    pub fn mount(&mut self) -> Result<()> {
+        for page in self.pages.values_mut() {
+            page.0.on_mount()?;
+        }
+        for component in self.components.iter_mut() {
+            component.0.on_mount()?;
+        }
+        self.mounted = true;
+        self.with_page(self.current_mode, |page| page.on_enter());
+        Ok(())
+    }
+
+    /// Run a hook on a page, logging failures so one page can't take the app down.
+    fn with_page(&mut self, page: Page, hook: impl FnOnce(&mut dyn Component) -> Result<()>) {
+        if let Some(view) = self.pages.get_mut(&page) {
+            if let Err(err) = hook(view.0.as_mut()) {
+                log::error!("{} page: {}", page, err);
+            }
+        }
+    }
+
     pub fn handle_mouse(&mut self, mouse_event: ratzilla::event::MouseEvent) {
-        // handle events for only current page
-        self.pages.iter_mut().for_each(|(page_type, page)| {
-            if *page_type == self.current_mode {
-                page.0.handle_mouse(mouse_event.clone()).ok();
+        if let Some(component) = self.capturing_component() {
+            component.0.handle_mouse(mouse_event);
+            return;
+        }
+        // Overlays first, from the top, they are drawn over the page
+        let handled = self
+            .components
+            .iter_mut()
+            .rev()
+            .any(|component| component.0.handle_mouse(mouse_event.clone()) == Some(true));
+        if handled {
+            return;
+        }
+        // Clicks focus the widget under the pointer, or nothing
+        if mouse_event.event == MouseEventKind::Pressed && mouse_event.button == MouseButton::Left {
+            let position = cell_position(&mouse_event, self.area.unwrap_or_default());
+            self.focus_widget(self.focus.hit(position));
+        }
+        if let Some(page) = self.pages.get_mut(&self.current_mode) {
+            page.0.handle_mouse(mouse_event);
+        }
+    }
+
+    /// Start recording the session, or stop and save the recording in the background.
+    fn toggle_recording(&mut self, surface: &mut Surface) {
+        match surface.stop_recording() {
+            Some(recorder) if recorder.is_empty() => self.send(Action::Notify(
+                Severity::Warning,
+                "Recording discarded".to_string(),
+                "Nothing was drawn while recording.".to_string(),
+            )),
+            Some(recorder) => {
+                let name = Field {
+                    value: recorder.file_name(),
+                    ..Field::new("File name")
+                };
+                self.unsaved_recording = Some(recorder);
+                let save = Action::SubmitForm(SAVE_RECORDING_FORM.to_string(), Vec::new());
+                self.send(Action::ShowModal(
+                    Modal::new("Save recording", "Name the file to save the recording to.")
+                        .field(name)
+                        .button("Save", Some(save))
+                        .button("Discard", None),
+                ));
+            }
+            None => {
+                // A discarded recording is only kept until the next one starts
+                self.unsaved_recording = None;
+                if let Err(err) = surface.start_recording() {
+                    self.send(Action::Notify(
+                        Severity::Error,
+                        "Recording failed".to_string(),
+                        err.to_string(),
+                    ));
+                    return;
+                }
+                let stop = match self.keymap.keys_for(self.current_mode, Command::Record) {
+                    Some(keys) => format!("Press {keys}"),
+                    None => "Run :record".to_string(),
+                };
+                self.send(Action::Notify(
+                    Severity::Info,
+                    "Recording".to_string(),
+                    format!("{stop} again to stop and save the recording."),
+                ));
             }
+        }
+    }
+
+    /// Move focus on the current page right away, so the next key already goes to `id`.
+    fn focus_widget(&mut self, id: Option<&'static str>) {
+        let mut follow_up = None;
+        self.with_page(self.current_mode, |page| {
+            follow_up = page.update(Action::Focus(id))?;
+            Ok(())
         });
+        if let Some(action) = follow_up {
+            self.send(action);
+        }
+    }
+
+    /// Top-most component that takes all input, e.g. an open modal.
+    fn capturing_component(&mut self) -> Option<&mut View> {
+        self.components
+            .iter_mut()
+            .rev()
+            .find(|component| component.0.captures_input())
+    }
+
+    /// Copy the mouse selection, or else the focused input, returning whether there was
+    /// anything to copy.
+    fn copy(&mut self) -> bool {
+        let text = selected_text().or_else(|| match self.capturing_component() {
+            Some(component) => component.0.copy_text(),
+            None => self
+                .pages
+                .get_mut(&self.current_mode)
+                .and_then(|page| page.0.copy_text()),
+        });
+        match text {
+            Some(text) => {
+                self.send(Action::Copy(text));
+                true
+            }
+            None => false,
+        }
     }
 
     pub fn handle_events(&mut self, key_event: KeyEvent) {
-        let mut handled = None;
-        // handle events for only current page
-        self.pages.iter_mut().for_each(|(page_type, page)| {
-            if *page_type == self.current_mode {
-                let handled_page = page.0.handle_events(key_event.clone());
-                if handled_page.is_some() {
-                    handled = handled_page;
+        if key_event.ctrl && key_event.code == KeyCode::Char('c') && self.copy() {
+            return;
+        }
+        if let Some(component) = self.capturing_component() {
+            component.0.handle_events(key_event);
+            return;
+        }
+        if key_event.code == KeyCode::Tab && !key_event.ctrl && !key_event.alt {
+            if let Some(id) = self.focus.cycle(!key_event.shift) {
+                self.focus_widget(Some(id));
+                return;
+            }
+        }
+        // Keys the page uses itself, e.g. while typing, don't reach the keymap
+        let handled = self
+            .pages
+            .get_mut(&self.current_mode)
+            .and_then(|page| page.0.handle_events(key_event.clone()));
+        if handled == Some(true) {
+            self.keymap.reset();
+            return;
+        }
+        match self.keymap.handle_key(self.current_mode, &key_event) {
+            KeyResult::Action(action) => self.send(action),
+            // An Esc nothing else wants dismisses the newest toast
+            KeyResult::Unbound if key_event.code == KeyCode::Esc => self.send(Action::CloseToast),
+            _ => {}
+        }
+    }
+
+    /// Drain the channel and dispatch every action, including the follow-ups returned by
+    /// pages and components.
+    ///
+    /// At most [`MAX_ACTIONS_PER_FRAME`] actions are dispatched per frame, anything left
+    /// after that is most likely components answering each other forever and is dropped.
+    fn handle_actions(&mut self, rx: &mut UnboundedReceiver<Action>) {
+        let mut queue = VecDeque::new();
+        while let Ok(action) = rx.try_recv() {
+            queue.push_back(action);
+        }
+        let mut dispatched = 0;
+        while let Some(action) = queue.pop_front() {
+            if dispatched == MAX_ACTIONS_PER_FRAME {
+                log::error!(
+                    "Dropping {} actions after dispatching {} in one frame, last was {:?}",
+                    queue.len() + 1,
+                    MAX_ACTIONS_PER_FRAME,
+                    action
+                );
+                break;
+            }
+            dispatched += 1;
+            queue.extend(self.dispatch(action.clone()));
+            self.update(action);
+        }
+    }
+
+    /// Hand an action to the current page and every component, collecting their follow-ups.
+    ///
+    /// Input only goes to the component capturing it, so pasting into an open modal doesn't
+    /// also fill the page under it.
+    fn dispatch(&mut self, action: Action) -> Vec<Action> {
+        let mut follow_ups = Vec::new();
+        let capturing = action.is_input()
+            && self
+                .components
+                .iter()
+                .any(|component| component.0.captures_input());
+        let views: Vec<&mut View> = if capturing {
+            self.capturing_component().into_iter().collect()
+        } else {
+            let current = self.pages.get_mut(&self.current_mode);
+            current
+                .into_iter()
+                .chain(self.components.iter_mut())
+                .collect()
+        };
+        for view in views {
+            match view.0.update(action.clone()) {
+                Ok(follow_up) => follow_ups.extend(follow_up),
+                Err(err) => log::error!("Failed to handle {:?}: {}", action, err),
+            }
+        }
+        follow_ups
+    }
+
+    /// App-wide handling of an action, after the pages and components have seen it.
+    fn update(&mut self, action: Action) {
+        // The browser can change the page from under a modal, e.g. with its back button,
+        // leaving behind whatever the modal was about
+        if matches!(
+            action,
+            Action::ChangePage(_) | Action::Back | Action::Forward
+        ) {
+            self.send(Action::ClosePopup);
+        }
+        match action {
+            Action::ChangePage(page) => {
+                self.navigate(page);
+            }
+            // The browser may have moved through its history on its own (back button,
+            // reload), so its URL wins over the app's history
+            Action::Back => {
+                let page = self.history.back(self.current_mode);
+                self.show_from_history(parse_page_from_url(page.unwrap_or(self.current_mode)));
+            }
+            Action::Forward => {
+                let page = self.history.forward(self.current_mode);
+                self.show_from_history(parse_page_from_url(page.unwrap_or(self.current_mode)));
+            }
+            Action::ToggleShowHelp => self.send(self.help()),
+            Action::TogglePalette => {
+                self.send(Action::ShowPalette(self.keymap.palette(self.current_mode)));
+            }
+            Action::SetBackend(backend_type) if backend_type == self.backend.chosen => {
+                log::info!("Already using the {} backend", backend_type);
+            }
+            Action::SetBackend(backend_type) => {
+                log::info!("Switching to the {} backend", backend_type);
+                self.backend_request = Some(backend_type);
+            }
+            Action::SetTheme(theme) => self.theme = theme,
+            Action::ToggleRecording => self.recording_request = true,
+            Action::SubmitForm(form, values) if form == SAVE_RECORDING_FORM => {
+                if let Some(recorder) = self.unsaved_recording.take() {
+                    let name = values
+                        .into_iter()
+                        .next()
+                        .map(|name| name.trim().to_string())
+                        .filter(|name| !name.is_empty())
+                        .unwrap_or_else(|| recorder.file_name());
+                    self.tasks
+                        .spawn(None, save_recording(name, recorder.to_cast()));
                 }
             }
-        });
-        if handled.is_none() || handled == Some(false) {
-            match self.input_mode {
-                InputMode::Normal => {
-                    match key_event.code {
-                        KeyCode::Char('q') => {
-                            // Exit application
-                            self.current_mode = Page::Settings;
-                        }
-                        KeyCode::Char('h') => self.current_mode = Page::Login,
-                        KeyCode::Char('m') => self.current_mode = Page::Help,
-                        _ => {}
-                    }
+            Action::GoBack => self.go_back(),
+            Action::GoForward => self.go_forward(),
+            Action::SubmitEmail(email) => {
+                self.email = Some(email.clone());
+                self.request_code(email);
+            }
+            Action::ResendCode => {
+                if let Some(email) = self.email.clone() {
+                    self.request_code(email);
                 }
-                InputMode::Other => {
-                    match key_event.code {
-                        KeyCode::Char('q') => {
-                            // Exit application
-                            self.current_mode = Page::Settings;
-                        }
-                        KeyCode::Char('h') => {
-                            self.current_mode = Page::Home;
-                        }
-                        _ => {}
+            }
+            Action::CodeSent(_) => {
+                self.notify_hidden(Page::Verify, action);
+                self.navigate(Page::Verify);
+            }
+            Action::SubmitCode(code) => match (self.tx.clone(), self.email.clone()) {
+                (Some(tx), Some(email)) => {
+                    let config = self.auth.clone();
+                    self.tasks.spawn(Some(Page::Verify), async move {
+                        auth::submit_code(config, email, code, tx).await;
+                        None::<Action>
+                    });
+                }
+                // E.g. after reloading the verify page, the code can't be checked without it
+                (_, None) => self.send(Action::Error("Enter your email first".into())),
+                (None, Some(_)) => {}
+            },
+            Action::StartSession(session) => match session.expiry() {
+                Ok(expiry) => {
+                    if let Err(err) = self.store.save(&session) {
+                        log::error!("Failed to persist session: {}", err);
                     }
+                    self.session = Some(session);
+                    self.expiry = expiry;
+                    self.send(Action::LoggedIn);
+                }
+                Err(err) => {
+                    log::error!("Rejecting session: {}", err);
+                    // The verify page waits for the session to arrive
+                    self.send(Action::Error(err.to_string()));
                 }
+            },
+            Action::LoggedIn => {
+                self.notify_hidden(Page::Login, action.clone());
+                self.notify_hidden(Page::Verify, action);
+                let target = self.redirect.take().unwrap_or(Page::LANDING);
+                self.navigate(target);
             }
+            // Nothing to confirm without a session
+            Action::Logout if self.session.is_some() => {
+                self.send(Action::ShowModal(Modal::confirm(
+                    "Log out",
+                    "End the session on this device?",
+                    Action::LoggedOut,
+                )));
+            }
+            Action::ClearHistory => self.history.clear(),
+            Action::Copy(text) => {
+                self.tasks.spawn(None, copy_to_clipboard(text));
+            }
+            Action::LoggedOut => {
+                self.session = None;
+                self.expiry = None;
+                if let Err(err) = self.store.clear() {
+                    log::error!("Failed to clear session: {}", err);
+                }
+                // Come back here once the user has logged in again
+                if self.current_mode.requires_auth() {
+                    self.redirect = Some(self.current_mode);
+                }
+                self.navigate(Page::Login);
+            }
+            _ => {}
         }
     }
 
-    fn handle_actions(&mut self, rx: &mut UnboundedReceiver<Action>) -> Result<Option<Action>> {
-        while let Ok(action) = rx.try_recv() {
-            match action {
-                Action::ChangePage(page) => {
-                    self.current_mode = page;
-                }
-                Action::SubmitEmail(email) => {
-                    // TODO: Subimt to api endpoint
-                    self.current_mode = Page::Settings;
-                    let tx = match self.tx.clone() {
-                        Some(tx) => tx,
-                        _ => return Ok(None),
-                    };
-                    // tokio::spawn(async move {
-                    //     tx.send(Action::EnterProcessing).unwrap();
-                    //     let req = reqwest::Client::new();
-                    //     match req
-                    //         .post("http://localhost:8080/api/auth/register")
-                    //         .bearer_auth("Some otkne")
-                    //         .send()
-                    //         .await
-                    //     {
-                    //         Ok(res) => {
-                    //             //convert to Calendar to return
-                    //             let data = res.text().await.unwrap_or_default();
-                    //             log::info!("Login successful: {:?}", data);
-                    //         }
-                    //         Err(err) => {
-                    //             log::error!("Failed to login: {}", err);
-                    //             tx.send(Action::EnterNormal).unwrap();
-                    //         }
-                    //     };
-                    // });
-                }
-                _ => {}
+    /// Visit `page`, recording the current page in the history.
+    fn navigate(&mut self, page: Page) {
+        // Picking a public page gives up on the one the user was sent to log in for
+        if !page.requires_auth() && !page.is_login() {
+            self.redirect = None;
+        }
+        let previous = self.current_mode;
+        self.show(page);
+        if self.current_mode != previous {
+            self.history.visit(previous);
+        }
+    }
+
+    /// Go back a page, through the browser's history when there is one.
+    fn go_back(&mut self) {
+        if self.history.can_go_back() && !go_back() {
+            self.send(Action::Back);
+        }
+    }
+
+    /// Go forward a page, through the browser's history when there is one.
+    fn go_forward(&mut self) {
+        if self.history.can_go_forward() && !go_forward() {
+            self.send(Action::Forward);
+        }
+    }
+
+    /// Switch to `page`, sending the user to log in first if the page needs a session.
+    ///
+    /// Pages are kept alive while hidden, so their state (input, scroll position, ...) is
+    /// still there when coming back. The requested page is remembered and shown after
+    /// `Action::LoggedIn`.
+    fn show(&mut self, page: Page) {
+        self.switch_to(page);
+        push_page(self.current_mode);
+    }
+
+    /// Switch to the page the browser moved to through its history.
+    ///
+    /// The browser is already on that page's entry, so when the guard sends the user to log
+    /// in instead, the entry is rewritten rather than pushed over.
+    fn show_from_history(&mut self, page: Page) {
+        self.switch_to(page);
+        replace_page(self.current_mode);
+    }
+
+    /// Make `page` the current page, or the login page if it needs a session, without
+    /// touching the URL.
+    fn switch_to(&mut self, page: Page) {
+        if page == Page::Help {
+            self.send(self.help());
+            return;
+        }
+        let page = if page.requires_auth() && self.session.is_none() {
+            self.redirect = Some(page);
+            Page::Login
+        } else {
+            page
+        };
+        if page != self.current_mode {
+            // Whatever the page was waiting for is no longer wanted
+            self.tasks.cancel(self.current_mode);
+            self.keymap.reset();
+        }
+        if page != self.current_mode && self.mounted {
+            self.with_page(self.current_mode, |page| page.on_leave());
+            self.with_page(page, |page| page.on_enter());
+        }
+        self.current_mode = page;
+    }
+
+    /// Help for the current page, closed again with the keys that open it.
+    fn help(&self) -> Action {
+        Action::ShowHelp(
+            self.keymap.help(self.current_mode),
+            self.keymap
+                .keys_for(self.current_mode, Command::Help)
+                .cloned(),
+        )
+    }
+
+    fn send(&self, action: Action) {
+        if let Some(tx) = self.tx.as_ref() {
+            tx.send(action).ok();
+        }
+    }
+
+    /// Log out once the session can no longer be used.
+    fn check_session(&mut self) {
+        if self.session.is_none() {
+            return;
+        }
+        if let Err(err) = session::now().and_then(|now| session::check_expiry(self.expiry, now)) {
+            log::info!("Session ended: {}", err);
+            self.session = None;
+            self.expiry = None;
+            self.send(Action::LoggedOut);
+            self.send(Action::Popup(
+                "Session expired".to_string(),
+                "Log in again to continue.".to_string(),
+            ));
+        }
+    }
+
+    /// Ask the auth API to email a login code.
+    fn request_code(&mut self, email: String) {
+        if let Some(tx) = self.tx.clone() {
+            let config = self.auth.clone();
+            // Sent from the login page, or the verify page when resending
+            self.tasks.spawn(Some(self.current_mode), async move {
+                auth::submit_email(config, email, tx).await;
+                None::<Action>
+            });
+        }
+    }
+
+    /// Forward an action to a page that isn't shown, e.g. the progress of the login flow.
+    ///
+    /// The current page already got it through [`App::dispatch`].
+    fn notify_hidden(&mut self, page: Page, action: Action) {
+        if page == self.current_mode {
+            return;
+        }
+        let mut follow_up = None;
+        self.with_page(page, |page| {
+            follow_up = page.update(action)?;
+            Ok(())
+        });
+        if let Some(action) = follow_up {
+            self.send(action);
+        }
+    }
+
+    /// Let every page and component lay itself out again when the terminal size changes.
+    fn check_resize(&mut self, area: Rect) {
+        if self.area == Some(area) {
+            return;
+        }
+        self.area = Some(area);
+        let pages: Vec<Page> = self.pages.keys().copied().collect();
+        for page in pages {
+            self.with_page(page, |page| page.on_resize(area));
+        }
+        for component in self.components.iter_mut() {
+            if let Err(err) = component.0.on_resize(area) {
+                log::error!("Failed to resize component: {}", err);
             }
         }
-        Ok(None)
     }
 
     pub fn run(
         &mut self,
         frame: &mut Frame,
         rx: &mut UnboundedReceiver<Action>,
-        tx: &UnboundedSender<Action>,
-    ) -> Result<()> {
+        _tx: &UnboundedSender<Action>,
+    ) {
         // Send over actions to be handled
-        self.handle_actions(rx)?;
+        self.tasks.run_pending();
+        self.check_session();
+        self.check_resize(frame.area());
+        self.handle_actions(rx);
+        let area = frame.area();
+        frame.buffer_mut().set_style(area, self.theme.style());
         // Show page
         match self.pages.get_mut(&self.current_mode) {
             Some(page) => {
                 page.0.draw(frame);
+                self.focus.set(page.0.focus_areas());
+            }
+            None => {
+                NotFound::new().draw(frame);
+                self.focus.set(Vec::new());
             }
-            None => NotFound::new().draw(frame),
+        }
+        // Overlays on top
+        for component in self.components.iter_mut() {
+            component.0.draw(frame);
         }
         // Handle the Window title
-        if let Some(get) = self.pages.get(&self.current_mode) {
+        if self.pages.contains_key(&self.current_mode) && browser_window().is_some() {
             utils::set_document_title(&format!("{} - {:?}", APP_NAME, self.current_mode)).ok();
         }
-        Ok(())
         //frame.render_effect(&mut self.intro_effect, area, Duration::from_millis(40));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{Harness, Step};
+
+    /// Widget of the current page that has focus.
+    fn focused(app: &App) -> Option<&'static str> {
+        app.pages
+            .get(&app.current_mode)?
+            .0
+            .focus_areas()
+            .into_iter()
+            .find(|area| area.focused)
+            .map(|area| area.id)
+    }
+
+    /// Frames until the login title is fully drawn, at 40ms each.
+    const LOGIN_INTRO_FRAMES: usize = 100;
+
+    #[test]
+    fn starts_on_the_login_page() {
+        let mut harness = Harness::new(80, 30);
+        assert_eq!(harness.app.current_mode, Page::Login);
+        assert!(harness.shows("Please enter your email to login."));
+    }
+
+    #[test]
+    fn tab_focuses_the_email_input() {
+        let mut harness = Harness::new(80, 30);
+        harness
+            .run([Step::Frame, Step::key(KeyCode::Tab)])
+            .run(Step::text("me@example.com"));
+        assert!(harness.shows("me@example.com"));
+        assert_eq!(focused(&harness.app), Some("email"));
+    }
+
+    #[test]
+    fn clicks_outside_the_input_blur_it() {
+        let mut harness = Harness::new(80, 30);
+        harness.run([Step::Frame, Step::key(KeyCode::Char('e')), Step::Settle]);
+        assert_eq!(focused(&harness.app), Some("email"));
+        harness.run([Step::click(0, 0), Step::Frame]);
+        assert_eq!(focused(&harness.app), None);
+    }
+
+    #[test]
+    fn clicking_a_toast_keeps_the_focus() {
+        let mut harness = Harness::new(80, 30);
+        harness.run([Step::Frame, Step::key(KeyCode::Char('e')), Step::Settle]);
+        harness.send(Action::Toast("Saved".to_string(), "All good".to_string()));
+        harness.settle();
+        // Toasts are stacked in the top right corner
+        harness.run([Step::click(76, 2), Step::Settle]);
+        assert_eq!(focused(&harness.app), Some("email"));
+    }
+
+    #[test]
+    fn pages_without_a_view_are_not_found() {
+        let mut harness = Harness::new(80, 30);
+        let session = Session {
+            token: "opaque".to_string(),
+            expires_at: None,
+        };
+        harness.send(Action::StartSession(session));
+        harness.settle();
+        assert_eq!(harness.app.current_mode, Page::LANDING);
+        harness.send(Action::ChangePage(Page::Home));
+        assert!(harness.shows("Page not found!"));
+    }
+
+    #[test]
+    fn key_bindings_only_lead_to_pages_with_a_view() {
+        let app = App::new();
+        for (from, to) in Page::ALL
+            .into_iter()
+            .flat_map(|from| Page::ALL.map(|to| (from, to)))
+        {
+            let shown = to == Page::Help || app.pages.contains_key(&to);
+            let bound = app.keymap.keys_for(from, Command::Page(to)).is_some();
+            assert!(shown || !bound, "{from} binds {to}, which has no view");
+        }
+    }
+
+    #[test]
+    fn public_pages_drop_the_login_redirect() {
+        let mut harness = Harness::new(80, 30);
+        harness.send(Action::ChangePage(Page::Home));
+        harness.settle();
+        assert_eq!(harness.app.redirect, Some(Page::Home));
+        harness.send(Action::ChangePage(Page::Help));
+        harness.send(Action::ChangePage(Page::Login));
+        harness.settle();
+        assert_eq!(harness.app.redirect, None);
+        let session = Session {
+            token: "opaque".to_string(),
+            expires_at: None,
+        };
+        harness.send(Action::StartSession(session));
+        harness.settle();
+        assert_eq!(harness.app.current_mode, Page::LANDING);
+    }
+
+    #[test]
+    fn paste_goes_to_the_open_modal_only() {
+        let mut harness = Harness::new(80, 30);
+        harness.send(Action::ChangePage(Page::Verify));
+        harness.settle();
+        harness.send(Action::ShowModal(Modal::confirm(
+            "Log out",
+            "End the session on this device?",
+            Action::LoggedOut,
+        )));
+        harness.settle();
+        harness.send(Action::Paste("987".to_string()));
+        harness.send(Action::ClosePopup);
+        assert!(!harness.shows("│ 9 │"));
+        harness.send(Action::Paste("987".to_string()));
+        assert!(harness.shows("│ 9 │"));
+    }
+
+    #[test]
+    fn help_page_opens_the_overlay() {
+        let mut harness = Harness::new(80, 30);
+        harness.send(Action::ChangePage(Page::Help));
+        assert!(harness.shows("Show or hide this help"));
+        assert_eq!(harness.app.current_mode, Page::Login);
+        harness.run([Step::key(KeyCode::Char('?'))]);
+        assert!(!harness.shows("Show or hide this help"));
+    }
+
+    #[test]
+    fn sessions_end_when_they_expire() {
+        let mut harness = Harness::new(80, 30);
+        let session = Session {
+            token: "opaque".to_string(),
+            expires_at: Some(1),
+        };
+        harness.send(Action::StartSession(session));
+        harness.settle();
+        assert_eq!(harness.app.session, None);
+        assert_eq!(harness.app.current_mode, Page::Login);
+        assert!(harness.shows("Session expired"));
+    }
+
+    #[test]
+    fn going_back_closes_the_modal() {
+        let mut harness = Harness::new(80, 30);
+        harness.send(Action::ChangePage(Page::Verify));
+        harness.settle();
+        harness.send(Action::ShowModal(Modal::confirm(
+            "Clear history",
+            "Forget the pages visited so far?",
+            Action::ClearHistory,
+        )));
+        assert!(harness.shows("Forget the pages"));
+        harness.send(Action::Back);
+        assert!(!harness.shows("Forget the pages"));
+        assert_eq!(harness.app.current_mode, Page::Login);
+    }
+
+    #[test]
+    fn codes_need_an_email() {
+        let mut harness = Harness::new(80, 30);
+        harness.send(Action::ChangePage(Page::Verify));
+        harness.settle();
+        harness.run(Step::text("123456"));
+        assert!(harness.shows("Verification failed: Enter your email first"));
+    }
+
+    #[test]
+    fn rejected_sessions_end_the_verification() {
+        let mut harness = Harness::new(80, 30);
+        harness.send(Action::ChangePage(Page::Verify));
+        harness.send(Action::EnterProcessing);
+        assert!(harness.shows("Verifying..."));
+        let session = Session {
+            token: String::new(),
+            expires_at: None,
+        };
+        harness.send(Action::StartSession(session));
+        harness.settle();
+        assert_eq!(harness.app.session, None);
+        assert!(harness.shows("Verification failed"));
+    }
+
+    #[test]
+    fn recordings_start_with_a_full_frame() {
+        let mut harness = Harness::new(60, 30);
+        harness.run([Step::Frame, Step::key(KeyCode::Char('R')), Step::Settle]);
+        harness.run([Step::Frames(3)]);
+        let recorder = harness.surface.stop_recording().unwrap();
+        let cast = recorder.to_cast();
+        let mut lines = cast.lines();
+        let header: serde_json::Value = serde_json::from_str(lines.next().unwrap()).unwrap();
+        assert_eq!(
+            (header["width"].as_u64(), header["height"].as_u64()),
+            (Some(60), Some(30))
+        );
+        let first: serde_json::Value = serde_json::from_str(lines.next().unwrap()).unwrap();
+        let output = first[2].as_str().unwrap();
+        assert!(output.starts_with("\x1b[?25l\x1b[0m\x1b[2J"));
+        // Blank cells are left to the cleared screen, so words are written one by one
+        assert!(["Please", "enter", "your", "email"]
+            .iter()
+            .all(|word| output.contains(word)));
+    }
+
+    #[test]
+    fn stopped_recordings_ask_for_a_file_name() {
+        let mut harness = Harness::new(80, 30);
+        harness.run([Step::Frame, Step::key(KeyCode::Char('R')), Step::Settle]);
+        harness.run([Step::Frames(3), Step::key(KeyCode::Char('R')), Step::Settle]);
+        assert!(harness.shows("Save recording"));
+        assert!(harness.shows(".cast"));
+        harness.run([Step::key(KeyCode::Enter)]);
+        assert!(!harness.shows("Save recording"));
+        assert!(harness.app.unsaved_recording.is_none());
+    }
+
+    #[test]
+    fn snapshot_login_page() {
+        let mut harness = Harness::new(60, 20);
+        // Past the title's intro effect, which dissolves it at random
+        harness
+            .run([Step::Frames(LOGIN_INTRO_FRAMES)])
+            .run([Step::key(KeyCode::Char('e')), Step::Settle])
+            .run(Step::text("me@example.com"));
+        harness.assert_snapshot("login_editing");
+    }
+
+    #[test]
+    fn snapshot_help_overlay() {
+        let mut harness = Harness::new(80, 30);
+        harness.run([Step::Frame, Step::key(KeyCode::Char('?'))]);
+        harness.assert_snapshot("help_overlay");
+    }
+
+    #[test]
+    fn snapshot_command_palette() {
+        let mut harness = Harness::new(80, 24);
+        harness.run([
+            Step::Frame,
+            Step::Action(Action::TogglePalette),
+            Step::Settle,
+        ]);
+        harness.run(Step::text("theme"));
+        harness.assert_snapshot("command_palette");
+    }
+}
@@ -3,7 +3,10 @@ mod pages;
 mod utils;
 
 use crate::app::App;
-use crate::utils::{Action, BackendType, MultiBackendBuilder};
+use crate::utils::auth::AuthConfig;
+use crate::utils::{
+    on_paste_event, on_route_event, Action, BackendType, MultiBackendBuilder, Surface,
+};
 use ratzilla::backend::cursor::CursorShape;
 use ratzilla::backend::dom::DomBackendOptions;
 use ratzilla::backend::webgl2::WebGl2BackendOptions;
@@ -11,12 +14,16 @@ use ratzilla::WebRenderer;
 use std::{cell::RefCell, io, rc::Rc};
 use tokio::sync::mpsc;
 
+#[allow(dead_code)]
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 const APP_NAME: &str = env!("CARGO_PKG_NAME");
+#[allow(dead_code)]
 const DESCRIPTION: &str = env!("CARGO_PKG_DESCRIPTION");
+#[allow(dead_code)]
 const AUTHOR: &str = env!("CARGO_PKG_AUTHORS");
 
-fn main() -> io::Result<()> {
+/// Builder of the terminal, called again whenever the backend is switched.
+fn terminal_builder() -> MultiBackendBuilder {
     let dom_options = DomBackendOptions::new(None, CursorShape::SteadyUnderScore);
 
     let webgl2_options = WebGl2BackendOptions::new()
@@ -24,12 +31,40 @@ fn main() -> io::Result<()> {
         .enable_console_debug_api()
         .enable_mouse_selection();
 
-    let terminal = MultiBackendBuilder::with_fallback(BackendType::Dom)
+    MultiBackendBuilder::with_fallback(BackendType::Dom)
         .dom_options(dom_options)
         .webgl2_options(webgl2_options)
-        .build_terminal()?;
+}
+
+/// Draw the first frame into memory and print it, to look at the app without a browser.
+#[cfg(feature = "headless")]
+fn print_first_frame() -> io::Result<()> {
+    let size = utils::HEADLESS_SIZE;
+    let mut surface = Surface::headless(size.width, size.height)?;
+    let (action_tx, mut action_rx) = mpsc::unbounded_channel::<Action>();
+    let mut app = App::new();
+    app.register_action_handler(action_tx.clone()).unwrap();
+    app.mount().unwrap();
+    surface.draw(|frame| app.run(frame, &mut action_rx, &action_tx))?;
+    let buffer = surface.terminal().backend().inner().buffer();
+    for line in buffer.map(utils::buffer_lines).unwrap_or_default() {
+        println!("{line}");
+    }
+    Ok(())
+}
 
-    let app = Rc::new(RefCell::new(App::new()));
+fn main() -> io::Result<()> {
+    // Without a browser there is nothing to draw on but memory
+    #[cfg(feature = "headless")]
+    if utils::browser_window().is_none() {
+        return print_first_frame();
+    }
+
+    let surface = Surface::new(terminal_builder)?;
+    let terminal = surface.terminal();
+
+    let auth = AuthConfig::from_environment().unwrap_or_default();
+    let app = Rc::new(RefCell::new(App::new().with_auth_config(auth)));
     // Register Handler for Events
     let (action_tx, mut action_rx) = mpsc::unbounded_channel::<Action>();
     terminal.on_key_event({
@@ -46,22 +81,32 @@ fn main() -> io::Result<()> {
             state.handle_mouse(mouse_event);
         }
     });
+    on_paste_event(action_tx.clone());
+    on_route_event(action_tx.clone());
     app.borrow_mut()
         .register_action_handler(action_tx.clone())
         .unwrap();
+    app.borrow_mut().set_backend(surface.selection().clone());
+    app.borrow_mut().mount().unwrap();
+    app.borrow_mut().restore_session();
     // Run the application
-    terminal.draw_web({
-        let render_state = app.clone();
-        move |frame| {
-            App::run(
-                &mut render_state.borrow_mut(),
-                frame,
-                &mut action_rx,
-                &action_tx,
-            )
-            .expect("Failed to run app");
-        }
-    });
+    surface.draw_web(
+        {
+            let render_state = app.clone();
+            move |frame| {
+                App::run(
+                    &mut render_state.borrow_mut(),
+                    frame,
+                    &mut action_rx,
+                    &action_tx,
+                );
+            }
+        },
+        {
+            let surface_state = app.clone();
+            move |surface| surface_state.borrow_mut().between_frames(surface)
+        },
+    );
 
     Ok(())
 }
@@ -1,114 +1,43 @@
-use std::cell::RefCell;
+//! Access to the system clipboard.
+use crate::pages::components::Severity;
+use crate::utils::{browser_window, Action, Error, Result};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
 
-use layout::Offset;
-use ratatui::{style::Stylize, Frame};
-use ratzilla::{
-    event::KeyEvent,
-    ratatui::{prelude::*, widgets::Clear},
-    widgets::Hyperlink,
-};
-use tachyonfx::{
-    fx::{self, RepeatMode},
-    CenteredShrink, Duration, Effect, EffectRenderer, Interpolation,
-};
-
-use crate::pages::Component;
-
-#[derive(Clone)]
-pub struct Clip {
-    intro_effect: Effect,
-    menu_effect: Option<Effect>,
-    text: RefCell<String>,
+/// Write `text` to the system clipboard.
+pub async fn set_clipboard(text: &str) -> Result<()> {
+    let clipboard = clipboard()?;
+    JsFuture::from(clipboard.write_text(text))
+        .await
+        .map_err(clipboard_error)?;
+    Ok(())
 }
 
-impl Default for Clip {
-    fn default() -> Self {
-        let text = format!(
-            "Press Ctrl+C to copy.\n\
-            Press Ctrl+V to paste."
-        );
-        Self {
-            text: RefCell::new(text),
-            menu_effect: None,
-            intro_effect: fx::sequence(&[
-                // fx::ping_pong(fx::sweep_in(
-                //     Motion::LeftToRight,
-                //     10,
-                //     0,
-                //     Color::Black,
-                //     EffectTimer::from_ms(3000, Interpolation::QuadIn),
-                // )),
-                fx::coalesce((3000, Interpolation::SineOut)),
-                fx::sleep(1000),
-                fx::repeat(
-                    fx::hsl_shift(
-                        Some([120.0, 25.0, 25.0]),
-                        None,
-                        (5000, Interpolation::Linear),
-                    ),
-                    RepeatMode::Forever,
-                ),
-            ]),
-        }
+/// Copy `text`, resolving to a toast with the outcome.
+pub async fn copy_to_clipboard(text: String) -> Action {
+    match set_clipboard(&text).await {
+        Ok(()) => Action::Notify(
+            Severity::Success,
+            "Copied".to_string(),
+            format!(
+                "{} characters copied to the clipboard.",
+                text.chars().count()
+            ),
+        ),
+        Err(err) => Action::Notify(Severity::Error, "Copy failed".to_string(), err.to_string()),
     }
 }
 
-impl Component for Clip {
-    fn draw(&mut self, frame: &mut Frame) {
-        Clear.render(frame.area(), frame.buffer_mut());
-        let area = frame.area().inner_centered(33, 2);
-        let main_text = Text::from(vec![
-            Line::from("| S Y M P I L |").bold(),
-            Line::from("Coming soon...").italic(),
-        ]);
-        //render_menu(f, state);
-        frame.render_widget(main_text.light_green().centered(), area);
-        let link = Hyperlink::new("https://github.com/orhun/ratzilla".red());
-        frame.render_widget(link, area.offset(Offset { x: 0, y: 4 }));
-        frame.render_effect(&mut self.intro_effect, area, Duration::from_millis(40));
-    }
-
-    fn handle_events(&mut self, key_event: KeyEvent) -> Option<bool> {
-        // match key_event.code {
-        //     KeyCode::Char('c') if key_event.ctrl => {
-        //         let clip = self.clone();
-        //         tokio::spawn({
-        //             let text = self.text.borrow().clone();
-        //             async move {
-        //                 clip.set_clipboard(&text).await;
-        //             }
-        //         });
-        //     }
-        //     KeyCode::Char('v') if key_event.ctrl => {
-        //         if let Ok(mut text) = self.text.try_borrow_mut() {
-        //             let clip = self.clone();
-        //             tokio::spawn(async move {
-        //                 let clipboard_text = self.get_clipboard().await;
-        //                 *text = clipboard_text;
-        //             });
-        //         }
-        //     }
-        //     _ => {}
-        // }
-        None
-    }
+fn clipboard() -> Result<web_sys::Clipboard> {
+    browser_window()
+        .map(|window| window.navigator().clipboard())
+        .ok_or_else(|| Error::Clipboard("No clipboard outside the browser".to_string()))
 }
-impl Clip {
-    pub fn new() -> Self {
-        Self::default()
-    }
-    async fn set_clipboard(&self, text: &str) {
-        let window = web_sys::window().unwrap();
-        let nav = window.navigator().clipboard();
-        let promise = nav.write_text(text);
-        wasm_bindgen_futures::JsFuture::from(promise).await.unwrap();
-    }
 
-    async fn get_clipboard(&self) -> String {
-        let window = web_sys::window().unwrap();
-        let nav = window.navigator().clipboard();
-        let promise = nav.read_text();
-        let result = wasm_bindgen_futures::JsFuture::from(promise).await.unwrap();
-        result.as_string().unwrap_or_default()
-    }
+fn clipboard_error(err: JsValue) -> Error {
+    let message = err
+        .dyn_ref::<web_sys::js_sys::Error>()
+        .map(|err| String::from(err.message()))
+        .unwrap_or_else(|| format!("{err:?}"));
+    Error::Clipboard(message)
 }
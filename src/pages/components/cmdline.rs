@@ -0,0 +1,264 @@
+use ratzilla::{
+    event::{KeyCode, KeyEvent},
+    ratatui::{
+        layout::{Constraint, Layout},
+        widgets::Clear,
+        Frame,
+    },
+};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::pages::components::TextInput;
+use crate::pages::Component;
+use crate::utils::{complete, parse_command_line, Action, Result};
+
+/// Lines remembered by the command line.
+const HISTORY_LIMIT: usize = 50;
+
+/// Vim-like command line at the bottom of the screen, opened with `Action::OpenCommandLine`.
+///
+/// Enter runs the line, a line that doesn't parse stays open with the error under it.
+/// Tab and Shift+Tab cycle through completions, Up/Down go through the lines run before.
+pub struct CommandLine {
+    input: TextInput,
+    open: bool,
+    /// Lines run so far, oldest first
+    history: Vec<String>,
+    /// Position in `history` while going through it
+    history_index: Option<usize>,
+    /// Line typed before going through the history
+    draft: String,
+    /// Completions of the line as it was before the first Tab, and the one shown
+    completions: Option<(Vec<String>, usize)>,
+    // Action Handler
+    tx: Option<UnboundedSender<Action>>,
+}
+
+impl Default for CommandLine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandLine {
+    pub fn new() -> Self {
+        let mut input = TextInput::new(":")
+            .placeholder("page settings, theme light, toast Hello... Tab completes")
+            .validator(|line| parse_command_line(line).map(|_| ()));
+        input.set_focused(true);
+        Self {
+            input,
+            open: false,
+            history: Vec::new(),
+            history_index: None,
+            draft: String::new(),
+            completions: None,
+            tx: None,
+        }
+    }
+
+    fn close(&mut self) {
+        self.open = false;
+        self.input.clear();
+        self.history_index = None;
+        self.completions = None;
+    }
+
+    /// Run the line, keeping the command line open if it doesn't parse.
+    fn submit(&mut self) {
+        let line = self.input.value().trim().to_string();
+        if line.is_empty() {
+            self.close();
+            return;
+        }
+        let Ok(action) = parse_command_line(&line) else {
+            self.input.validate().ok();
+            return;
+        };
+        if self.history.last() != Some(&line) {
+            self.history.push(line);
+            if self.history.len() > HISTORY_LIMIT {
+                self.history.remove(0);
+            }
+        }
+        self.close();
+        if let Some(tx) = self.tx.as_ref() {
+            tx.send(action).ok();
+        }
+    }
+
+    /// Show the next (or previous) completion of the line.
+    fn complete(&mut self, backwards: bool) {
+        let (candidates, index) = match self.completions.take() {
+            Some((candidates, index)) => {
+                let len = candidates.len();
+                let index = if backwards {
+                    (index + len - 1) % len
+                } else {
+                    (index + 1) % len
+                };
+                (candidates, index)
+            }
+            None => {
+                let candidates = complete(self.input.value());
+                if candidates.is_empty() {
+                    return;
+                }
+                let index = if backwards { candidates.len() - 1 } else { 0 };
+                (candidates, index)
+            }
+        };
+        self.input.set_value(&candidates[index]);
+        self.completions = Some((candidates, index));
+    }
+
+    /// Go to an older (or newer) line of the history.
+    fn recall(&mut self, older: bool) {
+        let index = match (self.history_index, older) {
+            (None, true) if !self.history.is_empty() => {
+                self.draft = self.input.value().to_string();
+                self.history.len() - 1
+            }
+            (Some(index), true) => index.saturating_sub(1),
+            (Some(index), false) if index + 1 < self.history.len() => index + 1,
+            (Some(_), false) => {
+                self.history_index = None;
+                let draft = std::mem::take(&mut self.draft);
+                self.input.set_value(&draft);
+                return;
+            }
+            _ => return,
+        };
+        self.history_index = Some(index);
+        self.input.set_value(&self.history[index]);
+    }
+}
+
+impl Component for CommandLine {
+    fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
+        self.tx = Some(tx);
+        Ok(())
+    }
+
+    fn captures_input(&self) -> bool {
+        self.open
+    }
+
+    fn copy_text(&mut self) -> Option<String> {
+        self.input.copy_text()
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::OpenCommandLine => self.open = true,
+            Action::Paste(text) if self.open => self.input.insert_str(&text),
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn handle_events(&mut self, key: KeyEvent) -> Option<bool> {
+        if !self.open {
+            return None;
+        }
+        if key.code != KeyCode::Tab {
+            self.completions = None;
+        }
+        match key.code {
+            KeyCode::Esc => self.close(),
+            KeyCode::Enter => self.submit(),
+            KeyCode::Tab => self.complete(key.shift),
+            KeyCode::Up => self.recall(true),
+            KeyCode::Down => self.recall(false),
+            // Like vim, deleting past the start leaves the command line
+            KeyCode::Backspace if self.input.value().is_empty() => self.close(),
+            _ => {
+                self.input.handle_key(&key);
+            }
+        }
+        Some(true)
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        if !self.open {
+            return;
+        }
+        let [_, area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).areas(frame.area());
+        frame.render_widget(Clear, area);
+        self.input.draw(frame, area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::Page;
+    use crate::utils::key;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    fn command_line() -> (CommandLine, tokio::sync::mpsc::UnboundedReceiver<Action>) {
+        let (tx, rx) = unbounded_channel();
+        let mut command_line = CommandLine::new();
+        command_line.register_action_handler(tx).unwrap();
+        command_line.update(Action::OpenCommandLine).unwrap();
+        (command_line, rx)
+    }
+
+    fn run(command_line: &mut CommandLine, line: &str) {
+        command_line.update(Action::OpenCommandLine).unwrap();
+        for c in line.chars() {
+            command_line.handle_events(key(KeyCode::Char(c)));
+        }
+        command_line.handle_events(key(KeyCode::Enter));
+    }
+
+    #[test]
+    fn enter_runs_the_command() {
+        let (mut command_line, mut rx) = command_line();
+        run(&mut command_line, "page help");
+        assert_eq!(rx.try_recv(), Ok(Action::ChangePage(Page::Help)));
+        assert!(!command_line.captures_input());
+    }
+
+    #[test]
+    fn invalid_lines_stay_open() {
+        let (mut command_line, mut rx) = command_line();
+        run(&mut command_line, "page");
+        assert!(rx.try_recv().is_err());
+        assert!(command_line.captures_input());
+        assert_eq!(command_line.input.value(), "page");
+        assert!(command_line.history.is_empty());
+    }
+
+    #[test]
+    fn tab_cycles_through_completions() {
+        let (mut command_line, _rx) = command_line();
+        for c in "theme ".chars() {
+            command_line.handle_events(key(KeyCode::Char(c)));
+        }
+        command_line.handle_events(key(KeyCode::Tab));
+        assert_eq!(command_line.input.value(), "theme dark");
+        command_line.handle_events(key(KeyCode::Tab));
+        assert_eq!(command_line.input.value(), "theme light");
+        command_line.handle_events(key(KeyCode::Tab));
+        assert_eq!(command_line.input.value(), "theme dark");
+    }
+
+    #[test]
+    fn up_and_down_walk_the_history() {
+        let (mut command_line, _rx) = command_line();
+        run(&mut command_line, "logout");
+        run(&mut command_line, "back");
+        command_line.update(Action::OpenCommandLine).unwrap();
+        command_line.handle_events(key(KeyCode::Char('x')));
+        command_line.handle_events(key(KeyCode::Up));
+        assert_eq!(command_line.input.value(), "back");
+        command_line.handle_events(key(KeyCode::Up));
+        command_line.handle_events(key(KeyCode::Up));
+        assert_eq!(command_line.input.value(), "logout");
+        command_line.handle_events(key(KeyCode::Down));
+        command_line.handle_events(key(KeyCode::Down));
+        assert_eq!(command_line.input.value(), "x");
+    }
+}
@@ -0,0 +1,337 @@
+use ratzilla::event::{KeyCode, KeyEvent};
+use ratzilla::ratatui::layout::{Constraint, Layout, Rect};
+use ratzilla::ratatui::style::{Color, Modifier, Style, Stylize};
+use ratzilla::ratatui::text::Line;
+use ratzilla::ratatui::widgets::Block;
+use ratzilla::ratatui::Frame;
+use tokio::sync::mpsc::UnboundedSender;
+use tui_textarea::{Input, Key, TextArea};
+
+use crate::pages::components::TextInput;
+use crate::pages::Component;
+use crate::utils::{Action, Result};
+
+/// Multi-line editor for composing messages and notes.
+///
+/// Enter submits the text as `Action::CompleteInput` and Shift+Enter starts a new line.
+/// Undo/redo are on Ctrl+Z/Ctrl+Y, Shift with the cursor keys selects, Ctrl+A selects
+/// everything and Ctrl+F searches. Other keys behave like in `tui-textarea`.
+pub struct Editor {
+    textarea: TextArea<'static>,
+    /// Title of the border
+    label: String,
+    /// Query while searching, `None` when not searching
+    search: Option<TextInput>,
+    /// Whether keys go to this editor, which shows the cursor
+    focused: bool,
+    // Action Handler
+    tx: Option<UnboundedSender<Action>>,
+}
+
+impl Default for Editor {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+impl Editor {
+    pub fn new(label: &str) -> Self {
+        let mut editor = Self {
+            textarea: TextArea::default(),
+            label: label.to_string(),
+            search: None,
+            focused: false,
+            tx: None,
+        };
+        editor
+            .textarea
+            .set_search_style(Style::default().bg(Color::Yellow).fg(Color::Black));
+        editor.textarea.set_cursor_line_style(Style::default());
+        editor.set_focused(false);
+        editor
+    }
+
+    pub fn placeholder(mut self, placeholder: &str) -> Self {
+        self.textarea.set_placeholder_text(placeholder);
+        self
+    }
+
+    /// The text, lines joined with `\n`.
+    pub fn value(&self) -> String {
+        self.textarea.lines().join("\n")
+    }
+
+    /// Replace the text, dropping the undo history.
+    pub fn set_value(&mut self, value: &str) {
+        let mut textarea = TextArea::new(value.lines().map(str::to_string).collect());
+        textarea.set_search_style(self.textarea.search_style());
+        textarea.set_cursor_line_style(self.textarea.cursor_line_style());
+        textarea.set_placeholder_text(self.textarea.placeholder_text());
+        textarea.set_cursor_style(self.textarea.cursor_style());
+        self.textarea = textarea;
+    }
+
+    pub fn clear(&mut self) {
+        self.set_value("");
+    }
+
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+        // The terminal cursor can't be placed inside the textarea widget, so it draws its own
+        let cursor = if focused {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default()
+        };
+        self.textarea.set_cursor_style(cursor);
+    }
+
+    /// Selected text, or all of it when nothing is selected.
+    pub fn copy_text(&mut self) -> Option<String> {
+        if self.textarea.is_selecting() {
+            self.textarea.copy();
+            return Some(self.textarea.yank_text());
+        }
+        let text = self.value();
+        (!text.is_empty()).then_some(text)
+    }
+
+    /// Insert text at the cursor, e.g. from a paste.
+    pub fn insert_str(&mut self, text: &str) {
+        self.textarea.insert_str(text);
+    }
+
+    /// Edit the text with `key`, returning whether the key was used.
+    pub fn handle_key(&mut self, key: &KeyEvent) -> bool {
+        if self.search.is_some() {
+            return self.handle_search_key(key);
+        }
+        match key.code {
+            KeyCode::Enter if key.shift => self.textarea.insert_newline(),
+            KeyCode::Enter => self.submit(),
+            KeyCode::Char('z') if key.ctrl && key.shift => {
+                self.textarea.redo();
+            }
+            KeyCode::Char('z') if key.ctrl => {
+                self.textarea.undo();
+            }
+            KeyCode::Char('y') if key.ctrl => {
+                self.textarea.redo();
+            }
+            KeyCode::Char('a') if key.ctrl => self.textarea.select_all(),
+            KeyCode::Char('f') if key.ctrl => {
+                self.search = Some(TextInput::new("Search").placeholder("Esc to close"));
+                if let Some(search) = self.search.as_mut() {
+                    search.set_focused(true);
+                }
+            }
+            KeyCode::Esc if self.textarea.is_selecting() => self.textarea.cancel_selection(),
+            KeyCode::Esc | KeyCode::Unidentified => return false,
+            _ => return self.textarea.input(to_input(key)),
+        }
+        true
+    }
+
+    fn handle_search_key(&mut self, key: &KeyEvent) -> bool {
+        let Some(search) = self.search.as_mut() else {
+            return false;
+        };
+        match key.code {
+            KeyCode::Esc => {
+                self.search = None;
+                self.textarea.set_search_pattern("").ok();
+            }
+            KeyCode::Enter | KeyCode::Down if !key.shift => {
+                self.textarea.search_forward(false);
+            }
+            KeyCode::Enter | KeyCode::Up => {
+                self.textarea.search_back(false);
+            }
+            _ => {
+                if search.handle_key(key) {
+                    let pattern = escape(search.value());
+                    self.textarea.set_search_pattern(pattern).ok();
+                    // Jump to the first match as the query is typed
+                    self.textarea.search_forward(true);
+                }
+            }
+        }
+        true
+    }
+
+    fn submit(&mut self) {
+        let text = self.value();
+        if text.trim().is_empty() {
+            return;
+        }
+        if let Some(tx) = self.tx.as_ref() {
+            tx.send(Action::CompleteInput(text)).ok();
+        }
+        self.clear();
+    }
+
+    /// Draw the editor into `area`, with the search box below it while searching.
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let border = if self.focused {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        };
+        let help = if self.focused {
+            Line::from("Enter to send, Shift+Enter for a new line").dark_gray()
+        } else {
+            Line::default()
+        };
+        self.textarea.set_block(
+            Block::bordered()
+                .title(self.label.clone())
+                .title_bottom(help.right_aligned())
+                .border_style(border),
+        );
+        match self.search.as_mut() {
+            Some(search) => {
+                let [text_area, search_area] =
+                    Layout::vertical([Constraint::Min(3), Constraint::Length(3)]).areas(area);
+                frame.render_widget(&self.textarea, text_area);
+                search.draw(frame, search_area);
+            }
+            None => frame.render_widget(&self.textarea, area),
+        }
+    }
+}
+
+impl Component for Editor {
+    fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
+        self.tx = Some(tx);
+        Ok(())
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        if let Action::Paste(text) = action {
+            if self.focused {
+                self.insert_str(&text);
+            }
+        }
+        Ok(None)
+    }
+
+    fn handle_events(&mut self, key: KeyEvent) -> Option<bool> {
+        self.handle_key(&key).then_some(true)
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        self.render(frame, frame.area());
+    }
+}
+
+/// Translate a ratzilla key into `tui-textarea` input.
+fn to_input(key: &KeyEvent) -> Input {
+    let key_code = match key.code {
+        KeyCode::Char(c) => Key::Char(c),
+        KeyCode::F(n) => Key::F(n),
+        KeyCode::Backspace => Key::Backspace,
+        KeyCode::Enter => Key::Enter,
+        KeyCode::Left => Key::Left,
+        KeyCode::Right => Key::Right,
+        KeyCode::Up => Key::Up,
+        KeyCode::Down => Key::Down,
+        KeyCode::Tab => Key::Tab,
+        KeyCode::Delete => Key::Delete,
+        KeyCode::Home => Key::Home,
+        KeyCode::End => Key::End,
+        KeyCode::PageUp => Key::PageUp,
+        KeyCode::PageDown => Key::PageDown,
+        KeyCode::Esc => Key::Esc,
+        KeyCode::Unidentified => Key::Null,
+    };
+    Input {
+        key: key_code,
+        ctrl: key.ctrl,
+        alt: key.alt,
+        shift: key.shift,
+    }
+}
+
+/// Escape regex syntax so searches match the query literally.
+fn escape(query: &str) -> String {
+    let mut escaped = String::with_capacity(query.len());
+    for c in query.chars() {
+        if "\\.+*?()|[]{}^$#&-~".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{ctrl, key, shift};
+    use tokio::sync::mpsc::unbounded_channel;
+
+    fn type_text(editor: &mut Editor, text: &str) {
+        for c in text.chars() {
+            editor.handle_key(&key(KeyCode::Char(c)));
+        }
+    }
+
+    #[test]
+    fn enter_submits_and_shift_enter_breaks_lines() {
+        let (tx, mut rx) = unbounded_channel();
+        let mut editor = Editor::new("Message");
+        editor.register_action_handler(tx).unwrap();
+        type_text(&mut editor, "hi");
+        editor.handle_key(&shift(KeyCode::Enter));
+        type_text(&mut editor, "there");
+        editor.handle_key(&key(KeyCode::Enter));
+        assert_eq!(
+            rx.try_recv(),
+            Ok(Action::CompleteInput("hi\nthere".to_string()))
+        );
+        assert_eq!(editor.value(), "");
+
+        // Blank text isn't sent
+        editor.handle_key(&key(KeyCode::Enter));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut editor = Editor::new("Note");
+        type_text(&mut editor, "ab");
+        editor.handle_key(&ctrl(KeyCode::Char('z')));
+        assert_eq!(editor.value(), "a");
+        editor.handle_key(&ctrl(KeyCode::Char('y')));
+        assert_eq!(editor.value(), "ab");
+    }
+
+    #[test]
+    fn selection_is_replaced_by_typing() {
+        let mut editor = Editor::new("Note");
+        type_text(&mut editor, "hello");
+        editor.handle_key(&shift(KeyCode::Left));
+        editor.handle_key(&shift(KeyCode::Left));
+        assert!(editor.textarea.is_selecting());
+        editor.handle_key(&key(KeyCode::Backspace));
+        assert_eq!(editor.value(), "hel");
+        editor.handle_key(&ctrl(KeyCode::Char('a')));
+        editor.handle_key(&key(KeyCode::Delete));
+        assert_eq!(editor.value(), "");
+    }
+
+    #[test]
+    fn search_moves_the_cursor_literally() {
+        let mut editor = Editor::new("Note");
+        editor.set_value("a.b\naxb\na.b");
+        editor.handle_key(&ctrl(KeyCode::Char('f')));
+        assert!(editor.search.is_some());
+        type_text(&mut editor, "a.b");
+        assert_eq!(editor.textarea.cursor(), (0, 0));
+        editor.handle_key(&key(KeyCode::Enter));
+        assert_eq!(editor.textarea.cursor(), (2, 0));
+        editor.handle_key(&key(KeyCode::Esc));
+        assert!(editor.search.is_none());
+        assert_eq!(editor.value(), "a.b\naxb\na.b");
+    }
+}
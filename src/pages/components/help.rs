@@ -0,0 +1,278 @@
+use ratzilla::{
+    event::{KeyCode, KeyEvent},
+    ratatui::{
+        layout::{Constraint, Flex, Layout},
+        prelude::*,
+        widgets::{Block, Clear, Paragraph},
+        Frame,
+    },
+};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::pages::components::TextInput;
+use crate::pages::Component;
+use crate::utils::{Action, HelpSection, KeyChord, KeySequence, Result};
+
+/// Width of the overlay, including its border.
+const HELP_WIDTH: u16 = 64;
+/// Width of the keys column.
+const KEYS_WIDTH: usize = 14;
+
+/// Key bindings of the current page and the global ones, drawn over the page.
+///
+/// The app opens it with `Action::ShowHelp`, which carries the bindings from the keymap
+/// and closes it again when it is already open. Typing filters the bindings, Up/Down
+/// scroll and Esc or the keys that opened it close it.
+pub struct Help {
+    sections: Vec<HelpSection>,
+    /// Keys bound to the help, which close it like they open it
+    close_keys: Option<KeySequence>,
+    open: bool,
+    search: TextInput,
+    /// First line shown
+    scroll: u16,
+    // Action Handler
+    tx: Option<UnboundedSender<Action>>,
+}
+
+impl Default for Help {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Help {
+    pub fn new() -> Self {
+        let mut search = TextInput::new("Search").placeholder("Type to filter the keys");
+        search.set_focused(true);
+        Self {
+            sections: Vec::new(),
+            close_keys: None,
+            open: false,
+            search,
+            scroll: 0,
+            tx: None,
+        }
+    }
+
+    /// Whether `key` is the help binding, when it is a single key.
+    fn is_close_key(&self, key: &KeyEvent) -> bool {
+        self.close_keys
+            .as_ref()
+            .is_some_and(|keys| keys.0 == [KeyChord::from(key)])
+    }
+
+    fn close(&mut self) {
+        self.open = false;
+        self.search.clear();
+        self.scroll = 0;
+    }
+
+    /// Sections with only the entries matching the search, empty sections left out.
+    fn filtered(&self) -> Vec<HelpSection> {
+        let query = self.search.value().trim().to_lowercase();
+        self.sections
+            .iter()
+            .map(|section| HelpSection {
+                title: section.title.clone(),
+                entries: section
+                    .entries
+                    .iter()
+                    .filter(|(keys, description)| {
+                        keys.to_lowercase().contains(&query)
+                            || description.to_lowercase().contains(&query)
+                    })
+                    .cloned()
+                    .collect(),
+            })
+            .filter(|section| !section.entries.is_empty())
+            .collect()
+    }
+
+    fn lines(&self) -> Vec<Line<'static>> {
+        let mut lines = Vec::new();
+        for section in self.filtered() {
+            if !lines.is_empty() {
+                lines.push(Line::default());
+            }
+            lines.push(Line::from(section.title).bold().yellow());
+            for (keys, description) in section.entries {
+                lines.push(Line::from(vec![
+                    format!("  {keys:<KEYS_WIDTH$}").cyan(),
+                    description.into(),
+                ]));
+            }
+        }
+        if lines.is_empty() {
+            lines.push(Line::from("No matching keys").italic().dark_gray());
+        }
+        lines
+    }
+}
+
+impl Component for Help {
+    fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
+        self.tx = Some(tx);
+        Ok(())
+    }
+
+    fn captures_input(&self) -> bool {
+        self.open
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::ShowHelp(..) if self.open => self.close(),
+            Action::ShowHelp(sections, close_keys) => {
+                self.sections = sections;
+                self.close_keys = close_keys;
+                self.open = true;
+            }
+            Action::Paste(text) if self.open => {
+                self.search.insert_str(&text);
+                self.scroll = 0;
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn handle_events(&mut self, key: KeyEvent) -> Option<bool> {
+        if !self.open {
+            return None;
+        }
+        match key.code {
+            KeyCode::Esc if !self.search.value().is_empty() => self.search.clear(),
+            KeyCode::Esc => self.close(),
+            _ if self.search.value().is_empty() && self.is_close_key(&key) => self.close(),
+            KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Down => self.scroll = self.scroll.saturating_add(1),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_add(10),
+            _ => {
+                if self.search.handle_key(&key) {
+                    self.scroll = 0;
+                }
+            }
+        }
+        Some(true)
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        if !self.open {
+            return;
+        }
+        let lines = self.lines();
+        let width = HELP_WIDTH.min(frame.area().width);
+        // Border, search box and the lines, as far as they fit
+        let height = (lines.len() as u16 + 5).min(frame.area().height);
+        let [area] = Layout::vertical([Constraint::Length(height)])
+            .flex(Flex::Center)
+            .areas(frame.area());
+        let [area] = Layout::horizontal([Constraint::Length(width)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let block = Block::bordered()
+            .title_top(Line::from("Keys").bold().centered())
+            .title_bottom(Line::from("Esc to close").dark_gray().right_aligned())
+            .border_style(Style::default().fg(Color::Yellow))
+            .style(Style::default().bg(Color::Black).fg(Color::White));
+        let inner = block.inner(area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+
+        let [search_area, list_area] =
+            Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(inner);
+        self.search.draw(frame, search_area);
+        // Don't scroll past the last line
+        let max_scroll = (lines.len() as u16).saturating_sub(list_area.height);
+        self.scroll = self.scroll.min(max_scroll);
+        frame.render_widget(
+            Paragraph::new(lines).scroll((self.scroll, 0)),
+            list_area.inner(Margin::new(1, 0)),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::key;
+    use ratzilla::ratatui::{backend::TestBackend, Terminal};
+
+    fn sections() -> Vec<HelpSection> {
+        vec![
+            HelpSection {
+                title: "Login page".to_string(),
+                entries: vec![("e".to_string(), "Start editing".to_string())],
+            },
+            HelpSection {
+                title: "Global".to_string(),
+                entries: vec![
+                    ("?".to_string(), "Show or hide this help".to_string()),
+                    ("g s".to_string(), "Go to the settings page".to_string()),
+                ],
+            },
+        ]
+    }
+
+    /// Opens the help, closed again by `keys`.
+    fn show(keys: &str) -> Action {
+        Action::ShowHelp(sections(), Some(keys.parse().unwrap()))
+    }
+
+    /// Text of the screen after drawing the overlay on it.
+    fn rendered(help: &mut Help, width: u16, height: u16) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|frame| help.draw(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer.content().iter().map(|cell| cell.symbol()).collect()
+    }
+
+    #[test]
+    fn show_help_toggles_the_overlay() {
+        let mut help = Help::new();
+        help.update(show("?")).unwrap();
+        assert!(help.captures_input());
+        assert!(rendered(&mut help, 80, 24).contains("Start editing"));
+
+        help.update(show("?")).unwrap();
+        assert!(!help.captures_input());
+        help.update(show("?")).unwrap();
+        help.handle_events(key(KeyCode::Char('?')));
+        assert!(!help.open);
+    }
+
+    #[test]
+    fn closes_on_the_keys_bound_to_it() {
+        let mut help = Help::new();
+        help.update(show("F1")).unwrap();
+        // Searched for, as it isn't bound to the help
+        help.handle_events(key(KeyCode::Char('?')));
+        assert!(help.open);
+        help.handle_events(key(KeyCode::Esc));
+        help.handle_events(key(KeyCode::F(1)));
+        assert!(!help.open);
+    }
+
+    #[test]
+    fn typing_filters_the_bindings() {
+        let mut help = Help::new();
+        help.update(show("?")).unwrap();
+        for c in "SETT".chars() {
+            help.handle_events(key(KeyCode::Char(c)));
+        }
+        let filtered = help.filtered();
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].entries[0].0, "g s");
+
+        // `?` is searched for once there is a query, the first Esc clears it
+        help.handle_events(key(KeyCode::Char('?')));
+        assert!(help.open);
+        help.handle_events(key(KeyCode::Esc));
+        assert_eq!(help.filtered().len(), 2);
+        help.handle_events(key(KeyCode::Esc));
+        assert!(!help.open);
+    }
+}
@@ -1,57 +1,109 @@
-use std::collections::HashMap;
-
-use derive_deref::{Deref, DerefMut};
+use ratzilla::event::KeyCode;
 use ratzilla::event::KeyEvent;
-use ratzilla::ratatui::layout::{Constraint, Layout, Position};
-use ratzilla::ratatui::style::{Modifier, Style, Stylize};
-use ratzilla::ratatui::text::{Line, Span, Text};
-use ratzilla::ratatui::widgets::{List, ListItem};
+use ratzilla::ratatui::layout::{Constraint, Layout, Rect};
+use ratzilla::ratatui::style::{Color, Modifier, Style, Stylize};
+use ratzilla::ratatui::text::{Line, Text};
+use ratzilla::ratatui::widgets::{Block, Paragraph};
+use ratzilla::ratatui::widgets::{List, ListItem, ListState};
 use ratzilla::ratatui::Frame;
-use ratzilla::ratatui::{
-    style::Color,
-    widgets::{Block, Paragraph},
-};
-use ratzilla::{event::KeyCode, WebRenderer};
-use tachyonfx::{Effect, EffectRenderer};
-
-use crate::app::App;
+
+use crate::pages::components::Editor;
 use crate::pages::Component;
+use crate::utils::{Action, FocusArea, KeySequence, Result};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Focus id of the message input
+const INPUT: &str = "input";
+/// Focus id of the list of recorded messages
+const MESSAGES: &str = "messages";
 
 pub enum InputMode {
     Normal,
     Editing,
+    /// Going through the recorded messages
+    Browsing,
 }
 
 /// App holds the state of the application
 pub struct Message {
-    /// Current value of the input box
-    input: String,
-    /// Position of cursor in the editor area.
-    character_index: usize,
+    /// Message input
+    input: Editor,
     /// Current input mode
     input_mode: InputMode,
     /// History of recorded messages
     messages: Vec<String>,
+    /// Selected message while browsing
+    list: ListState,
+    /// Keys that start editing, for the hint
+    edit_keys: String,
+    /// Where the input and the messages were last drawn
+    input_area: Rect,
+    messages_area: Rect,
 }
 
 impl Component for Message {
+    fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
+        self.input.register_action_handler(tx)
+    }
+
+    fn focus_areas(&self) -> Vec<FocusArea> {
+        vec![
+            FocusArea::new(
+                INPUT,
+                self.input_area,
+                matches!(self.input_mode, InputMode::Editing),
+            ),
+            FocusArea::new(
+                MESSAGES,
+                self.messages_area,
+                matches!(self.input_mode, InputMode::Browsing),
+            ),
+        ]
+    }
+
+    fn copy_text(&mut self) -> Option<String> {
+        match self.input_mode {
+            InputMode::Normal => None,
+            InputMode::Editing => self.input.copy_text(),
+            InputMode::Browsing => self
+                .list
+                .selected()
+                .and_then(|i| self.messages.get(i).cloned()),
+        }
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            // Sent by the editor on Enter
+            Action::CompleteInput(text) => self.messages.push(text),
+            Action::EnterInput => self.set_input_mode(InputMode::Editing),
+            Action::Focus(id) => self.set_input_mode(match id {
+                Some(INPUT) => InputMode::Editing,
+                Some(MESSAGES) => InputMode::Browsing,
+                _ => InputMode::Normal,
+            }),
+            action => return self.input.update(action),
+        }
+        Ok(None)
+    }
+
     fn handle_events(&mut self, key_event: KeyEvent) -> Option<bool> {
         match self.input_mode {
-            InputMode::Normal => {
-                if let KeyCode::Char('e') = key_event.code {
-                    self.input_mode = InputMode::Editing;
+            // Bindings are in the keymap
+            InputMode::Normal => None,
+            InputMode::Editing => {
+                if !self.input.handle_key(&key_event) && key_event.code == KeyCode::Esc {
+                    self.set_input_mode(InputMode::Normal);
                 }
-                None
+                Some(true)
             }
-            InputMode::Editing => {
+            InputMode::Browsing => {
                 match key_event.code {
-                    KeyCode::Enter => self.submit_message(),
-                    KeyCode::Char(to_insert) => self.enter_char(to_insert),
-                    KeyCode::Backspace => self.delete_char(),
-                    KeyCode::Left => self.move_cursor_left(),
-                    KeyCode::Right => self.move_cursor_right(),
-                    KeyCode::Esc => self.input_mode = InputMode::Normal,
-                    _ => {}
+                    KeyCode::Up => self.list.select_previous(),
+                    KeyCode::Down => self.list.select_next(),
+                    KeyCode::Esc => self.set_input_mode(InputMode::Normal),
+                    // Everything else still goes to the keymap
+                    _ => return None,
                 }
                 Some(true)
             }
@@ -61,14 +113,18 @@ impl Component for Message {
     fn draw(&mut self, frame: &mut Frame) {
         let vertical = Layout::vertical([
             Constraint::Length(1),
-            Constraint::Length(3),
+            Constraint::Length(8),
             Constraint::Min(1),
         ]);
         let [help_area, input_area, messages_area] = vertical.areas(frame.area());
 
         let (msg, style) = match self.input_mode {
             InputMode::Normal => (
-                vec!["Press ".into(), "e".bold(), " to start editing.".bold()],
+                vec![
+                    "Press ".into(),
+                    self.edit_keys.clone().bold(),
+                    " to start editing.".bold(),
+                ],
                 Style::default().add_modifier(Modifier::RAPID_BLINK),
             ),
             InputMode::Editing => (
@@ -77,7 +133,21 @@ impl Component for Message {
                     "Esc".bold(),
                     " to stop editing, ".into(),
                     "Enter".bold(),
-                    " to record the message".into(),
+                    " to record the message, ".into(),
+                    "Shift+Enter".bold(),
+                    " for a new line".into(),
+                ],
+                Style::default(),
+            ),
+            InputMode::Browsing => (
+                vec![
+                    "Press ".into(),
+                    "Up/Down".bold(),
+                    " to pick a message, ".into(),
+                    "Ctrl+C".bold(),
+                    " to copy it, ".into(),
+                    "Esc".bold(),
+                    " to stop browsing".into(),
                 ],
                 Style::default(),
             ),
@@ -86,112 +156,117 @@ impl Component for Message {
         let help_message = Paragraph::new(text);
         frame.render_widget(help_message, help_area);
 
-        let input = Paragraph::new(self.input.as_str())
-            .style(match self.input_mode {
-                InputMode::Normal => Style::default(),
-                InputMode::Editing => Style::default().fg(Color::Yellow),
-            })
-            .block(Block::bordered().title("Input"));
-        frame.render_widget(input, input_area);
-        match self.input_mode {
-            // Hide the cursor. `Frame` does this by default, so we don't need to do anything here
-            InputMode::Normal => {}
-
-            // Make the cursor visible and ask ratatui to put it at the specified coordinates after
-            // rendering
-            InputMode::Editing => frame.set_cursor_position(Position::new(
-                // Draw the cursor at the current position in the input field.
-                // This position can be controlled via the left and right arrow key
-                input_area.x + self.character_index as u16 + 1,
-                // Move one line down, from the border to the input line
-                input_area.y + 1,
-            )),
-        }
+        self.input.render(frame, input_area);
+        self.input_area = input_area;
+        self.messages_area = messages_area;
 
         let messages: Vec<ListItem> = self
             .messages
             .iter()
             .enumerate()
             .map(|(i, m)| {
-                let content = Line::from(Span::raw(format!("{i}: {m}")));
+                // Messages can span several lines
+                let content = Text::raw(format!("{i}: {m}"));
                 ListItem::new(content)
             })
             .collect();
-        let messages = List::new(messages).block(Block::bordered().title("Messages"));
-        frame.render_widget(messages, messages_area);
+        let mut block = Block::bordered().title("Messages");
+        if matches!(self.input_mode, InputMode::Browsing) {
+            block = block.border_style(Style::default().fg(Color::Yellow));
+        }
+        let messages = List::new(messages)
+            .block(block)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(messages, messages_area, &mut self.list);
     }
 }
 
 impl Message {
     pub fn new() -> Self {
         Self {
-            input: String::new(),
+            input: Editor::new("Input").placeholder("Type a message"),
             input_mode: InputMode::Normal,
             messages: Vec::new(),
-            character_index: 0,
+            list: ListState::default(),
+            edit_keys: "Tab".to_string(),
+            input_area: Rect::default(),
+            messages_area: Rect::default(),
         }
     }
-    fn move_cursor_left(&mut self) {
-        let cursor_moved_left = self.character_index.saturating_sub(1);
-        self.character_index = self.clamp_cursor(cursor_moved_left);
-    }
-
-    fn move_cursor_right(&mut self) {
-        let cursor_moved_right = self.character_index.saturating_add(1);
-        self.character_index = self.clamp_cursor(cursor_moved_right);
-    }
 
-    fn enter_char(&mut self, new_char: char) {
-        let index = self.byte_index();
-        self.input.insert(index, new_char);
-        self.move_cursor_right();
+    /// Keys bound to start editing, shown in the hint. Tab always works, as it moves the
+    /// focus to the input.
+    pub fn edit_keys(mut self, keys: Option<&KeySequence>) -> Self {
+        if let Some(keys) = keys {
+            self.edit_keys = keys.to_string();
+        }
+        self
     }
-
-    /// Returns the byte index based on the character position.
-    ///
-    /// Since each character in a string can contain multiple bytes, it's necessary to calculate
-    /// the byte index based on the index of the character.
-    fn byte_index(&self) -> usize {
+    fn set_input_mode(&mut self, input_mode: InputMode) {
         self.input
-            .char_indices()
-            .map(|(i, _)| i)
-            .nth(self.character_index)
-            .unwrap_or(self.input.len())
-    }
-
-    fn delete_char(&mut self) {
-        let is_not_cursor_leftmost = self.character_index != 0;
-        if is_not_cursor_leftmost {
-            // Method "remove" is not used on the saved text for deleting the selected char.
-            // Reason: Using remove on String works on bytes instead of the chars.
-            // Using remove would require special care because of char boundaries.
-
-            let current_index = self.character_index;
-            let from_left_to_current_index = current_index - 1;
-
-            // Getting all characters before the selected character.
-            let before_char_to_delete = self.input.chars().take(from_left_to_current_index);
-            // Getting all characters after selected character.
-            let after_char_to_delete = self.input.chars().skip(current_index);
-
-            // Put all characters together except the selected one.
-            // By leaving the selected one out, it is forgotten and therefore deleted.
-            self.input = before_char_to_delete.chain(after_char_to_delete).collect();
-            self.move_cursor_left();
+            .set_focused(matches!(input_mode, InputMode::Editing));
+        match input_mode {
+            // Start from the latest message
+            InputMode::Browsing if self.list.selected().is_none() && !self.messages.is_empty() => {
+                self.list.select(Some(self.messages.len() - 1))
+            }
+            InputMode::Browsing => {}
+            _ => self.list.select(None),
         }
+        self.input_mode = input_mode;
     }
+}
 
-    fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
-        new_cursor_pos.clamp(0, self.input.chars().count())
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::key;
+    use crate::utils::{buffer_lines, headless_terminal, FpsTrackingBackend};
 
-    fn reset_cursor(&mut self) {
-        self.character_index = 0;
+    #[test]
+    fn draws_recorded_messages() {
+        let mut message = Message::new();
+        message
+            .update(Action::CompleteInput("hello".to_string()))
+            .unwrap();
+        message.update(Action::Focus(Some(MESSAGES))).unwrap();
+        let mut terminal = headless_terminal::<FpsTrackingBackend>(50, 15).unwrap();
+        terminal.draw(|frame| message.draw(frame)).unwrap();
+        let lines = buffer_lines(terminal.backend().buffer().unwrap());
+        assert!(lines.iter().any(|line| line.contains("Messages")));
+        assert!(lines.iter().any(|line| line.contains("0: hello")));
+        assert!(lines.iter().any(|line| line.contains("to pick a message")));
     }
 
-    fn submit_message(&mut self) {
-        self.messages.push(self.input.clone());
-        self.input.clear();
-        self.reset_cursor();
+    #[test]
+    fn focus_moves_between_input_and_messages() {
+        let mut message = Message::new();
+        message
+            .update(Action::CompleteInput("first".to_string()))
+            .unwrap();
+        message
+            .update(Action::CompleteInput("second".to_string()))
+            .unwrap();
+
+        message.update(Action::Focus(Some(MESSAGES))).unwrap();
+        assert!(matches!(message.input_mode, InputMode::Browsing));
+        assert_eq!(message.copy_text(), Some("second".to_string()));
+        assert_eq!(message.handle_events(key(KeyCode::Up)), Some(true));
+        assert_eq!(message.copy_text(), Some("first".to_string()));
+        assert_eq!(message.handle_events(key(KeyCode::Char('q'))), None);
+
+        message.update(Action::Focus(Some(INPUT))).unwrap();
+        assert!(matches!(message.input_mode, InputMode::Editing));
+        let focused: Vec<_> = message
+            .focus_areas()
+            .into_iter()
+            .filter(|area| area.focused)
+            .map(|area| area.id)
+            .collect();
+        assert_eq!(focused, vec![INPUT]);
+
+        message.update(Action::Focus(None)).unwrap();
+        assert!(message.copy_text().is_none());
+        assert!(message.focus_areas().iter().all(|area| !area.focused));
     }
 }
@@ -1,6 +1,18 @@
 mod clip;
+mod cmdline;
+mod editor;
+mod help;
 mod message;
+mod palette;
+mod popup;
 mod textinput;
+mod toast;
 pub use clip::*;
+pub use cmdline::*;
+pub use editor::*;
+pub use help::*;
 pub use message::*;
+pub use palette::*;
+pub use popup::*;
 pub use textinput::*;
+pub use toast::*;
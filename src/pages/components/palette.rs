@@ -0,0 +1,347 @@
+use ratzilla::{
+    event::{KeyCode, KeyEvent},
+    ratatui::{
+        layout::{Constraint, Flex, Layout, Rect},
+        prelude::*,
+        widgets::{Block, Clear, Paragraph},
+        Frame,
+    },
+};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::pages::components::TextInput;
+use crate::pages::Component;
+use crate::utils::{Action, Result};
+
+/// Width of the palette, including its border.
+const PALETTE_WIDTH: u16 = 60;
+/// Entries listed at once, the list scrolls to keep the selected one in view.
+const MAX_ROWS: usize = 10;
+
+/// What an entry of the palette does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    Page,
+    Command,
+    Setting,
+}
+
+impl EntryKind {
+    /// Get the string representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntryKind::Page => "page",
+            EntryKind::Command => "command",
+            EntryKind::Setting => "setting",
+        }
+    }
+}
+
+/// Something the palette can run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaletteEntry {
+    pub kind: EntryKind,
+    pub title: String,
+    /// Keys that do the same, shown as a hint
+    pub keys: Option<String>,
+    /// Dispatched when the entry is chosen
+    pub action: Action,
+}
+
+/// Command palette, fuzzy searching everything there is a command for.
+///
+/// The app opens it with `Action::ShowPalette`, which carries the entries, and closes it
+/// again when it is already open. Up/Down pick an entry, Enter runs it and Esc closes.
+pub struct Palette {
+    entries: Vec<PaletteEntry>,
+    open: bool,
+    search: TextInput,
+    /// Entries matching the search, best first
+    matches: Vec<usize>,
+    /// Index into `matches`
+    selected: usize,
+    // Action Handler
+    tx: Option<UnboundedSender<Action>>,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Palette {
+    pub fn new() -> Self {
+        let mut search = TextInput::new("Run").placeholder("Type a page, command or setting");
+        search.set_focused(true);
+        Self {
+            entries: Vec::new(),
+            open: false,
+            search,
+            matches: Vec::new(),
+            selected: 0,
+            tx: None,
+        }
+    }
+
+    fn open(&mut self, entries: Vec<PaletteEntry>) {
+        self.entries = entries;
+        self.open = true;
+        self.search.clear();
+        self.refresh();
+    }
+
+    fn close(&mut self) {
+        self.open = false;
+        self.search.clear();
+    }
+
+    /// Match the entries against the search again, selecting the best match.
+    fn refresh(&mut self) {
+        let query = self.search.value();
+        let mut scored: Vec<(i32, usize)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, entry)| fuzzy_score(query, &entry.title).map(|score| (score, i)))
+            .collect();
+        // Stable, so equally good matches keep their order
+        scored.sort_by_key(|(score, _)| -score);
+        self.matches = scored.into_iter().map(|(_, i)| i).collect();
+        self.selected = 0;
+    }
+
+    fn select(&mut self, offset: isize) {
+        if !self.matches.is_empty() {
+            let len = self.matches.len() as isize;
+            self.selected = (self.selected as isize + offset).rem_euclid(len) as usize;
+        }
+    }
+
+    /// Close the palette and dispatch the selected entry.
+    fn run(&mut self) {
+        let action = self
+            .matches
+            .get(self.selected)
+            .map(|&i| self.entries[i].action.clone());
+        self.close();
+        if let (Some(action), Some(tx)) = (action, self.tx.as_ref()) {
+            tx.send(action).ok();
+        }
+    }
+
+    fn draw_entry(frame: &mut Frame, entry: &PaletteEntry, selected: bool, area: Rect) {
+        let style = if selected {
+            Style::default().bg(Color::Yellow).fg(Color::Black)
+        } else {
+            Style::default()
+        };
+        let keys = entry.keys.as_deref().unwrap_or_default();
+        let [kind_area, title_area, keys_area] = Layout::horizontal([
+            Constraint::Length(9),
+            Constraint::Min(0),
+            Constraint::Length(keys.chars().count() as u16 + 1),
+        ])
+        .areas(area);
+        frame.render_widget(Paragraph::new("").style(style), area);
+        frame.render_widget(
+            Paragraph::new(Line::from(entry.kind.as_str()).italic())
+                .style(style.add_modifier(Modifier::DIM)),
+            kind_area,
+        );
+        frame.render_widget(
+            Paragraph::new(entry.title.as_str()).style(style),
+            title_area,
+        );
+        frame.render_widget(
+            Paragraph::new(Line::from(keys).right_aligned()).style(style.fg(Color::DarkGray)),
+            keys_area,
+        );
+    }
+}
+
+impl Component for Palette {
+    fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
+        self.tx = Some(tx);
+        Ok(())
+    }
+
+    fn captures_input(&self) -> bool {
+        self.open
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::ShowPalette(_) if self.open => self.close(),
+            Action::ShowPalette(entries) => self.open(entries),
+            Action::Paste(text) if self.open => {
+                self.search.insert_str(&text);
+                self.refresh();
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn handle_events(&mut self, key: KeyEvent) -> Option<bool> {
+        if !self.open {
+            return None;
+        }
+        match key.code {
+            KeyCode::Esc => self.close(),
+            KeyCode::Enter => self.run(),
+            KeyCode::Up => self.select(-1),
+            KeyCode::Down | KeyCode::Tab => self.select(1),
+            KeyCode::Char('p') if key.ctrl => self.select(-1),
+            KeyCode::Char('n') if key.ctrl => self.select(1),
+            _ => {
+                if self.search.handle_key(&key) {
+                    self.refresh();
+                }
+            }
+        }
+        Some(true)
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        if !self.open {
+            return;
+        }
+        let rows = self.matches.len().clamp(1, MAX_ROWS);
+        let width = PALETTE_WIDTH.min(frame.area().width);
+        let height = (rows as u16 + 5).min(frame.area().height);
+        // Near the top, where the eyes are when typing
+        let [_, area] = Layout::vertical([
+            Constraint::Length(frame.area().height / 6),
+            Constraint::Length(height),
+        ])
+        .flex(Flex::Start)
+        .areas(frame.area());
+        let [area] = Layout::horizontal([Constraint::Length(width)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let block = Block::bordered()
+            .title_top(Line::from("Command palette").bold().centered())
+            .border_style(Style::default().fg(Color::Yellow))
+            .style(Style::default().bg(Color::Black).fg(Color::White));
+        let inner = block.inner(area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+
+        let [search_area, list_area] =
+            Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(inner);
+        self.search.draw(frame, search_area);
+        if self.matches.is_empty() {
+            frame.render_widget(
+                Paragraph::new(Line::from("Nothing matches").italic().dark_gray()),
+                list_area.inner(Margin::new(1, 0)),
+            );
+            return;
+        }
+        // Scroll just enough to keep the selection in view
+        let first = self.selected.saturating_sub(MAX_ROWS - 1);
+        let areas = Layout::vertical(vec![Constraint::Length(1); rows])
+            .split(list_area.inner(Margin::new(1, 0)));
+        for (row, &i) in self.matches.iter().skip(first).take(rows).enumerate() {
+            let selected = first + row == self.selected;
+            Self::draw_entry(frame, &self.entries[i], selected, areas[row]);
+        }
+    }
+}
+
+/// Score how well `query` matches `text` as a subsequence, ignoring case, `None` when it
+/// doesn't match at all.
+///
+/// Characters right after the previous match and at the start of a word count the most,
+/// so `gs` prefers "Go to settings" over "Log out of sessions".
+fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    for q in query.chars().filter(|c| !c.is_whitespace()) {
+        let index = (next..text.len()).find(|&i| text[i].to_lowercase().eq(q.to_lowercase()))?;
+        score += 1;
+        if index > 0 && index == next {
+            score += 4;
+        }
+        if index == 0 || !text[index - 1].is_alphanumeric() {
+            score += 3;
+        }
+        // Skipped characters count against the match, up to a point
+        score -= (index - next).min(3) as i32;
+        next = index + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::Page;
+    use crate::utils::key;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    fn entry(title: &str, action: Action) -> PaletteEntry {
+        PaletteEntry {
+            kind: EntryKind::Command,
+            title: title.to_string(),
+            keys: None,
+            action,
+        }
+    }
+
+    fn palette() -> Palette {
+        let mut palette = Palette::new();
+        palette
+            .update(Action::ShowPalette(vec![
+                entry("Log out", Action::Logout),
+                entry(
+                    "Go to the settings page",
+                    Action::ChangePage(Page::Settings),
+                ),
+                entry("Go to the login page", Action::ChangePage(Page::Login)),
+            ]))
+            .unwrap();
+        palette
+    }
+
+    fn type_text(palette: &mut Palette, text: &str) {
+        for c in text.chars() {
+            palette.handle_events(key(KeyCode::Char(c)));
+        }
+    }
+
+    #[test]
+    fn fuzzy_matches_prefer_word_starts() {
+        assert!(fuzzy_score("xyz", "Go to settings").is_none());
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+        assert!(fuzzy_score("gs", "Go to settings") > fuzzy_score("gs", "Log out of sessions"));
+        assert!(fuzzy_score("set", "Settings") > fuzzy_score("set", "Reset"));
+    }
+
+    #[test]
+    fn enter_runs_the_best_match() {
+        let (tx, mut rx) = unbounded_channel();
+        let mut palette = palette();
+        palette.register_action_handler(tx).unwrap();
+        type_text(&mut palette, "login");
+        assert_eq!(palette.matches, vec![2]);
+        palette.handle_events(key(KeyCode::Enter));
+        assert_eq!(rx.try_recv(), Ok(Action::ChangePage(Page::Login)));
+        assert!(!palette.captures_input());
+    }
+
+    #[test]
+    fn selection_wraps_around() {
+        let (tx, mut rx) = unbounded_channel();
+        let mut palette = palette();
+        palette.register_action_handler(tx).unwrap();
+        palette.handle_events(key(KeyCode::Up));
+        assert_eq!(palette.selected, 2);
+        palette.handle_events(key(KeyCode::Down));
+        palette.handle_events(key(KeyCode::Esc));
+        assert!(!palette.captures_input());
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,500 @@
+use ratzilla::{
+    event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind},
+    ratatui::{
+        layout::{Constraint, Flex, Layout, Rect},
+        prelude::*,
+        widgets::{Block, Clear, Paragraph, Wrap},
+        Frame,
+    },
+};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::pages::components::TextInput;
+use crate::pages::Component;
+use crate::utils::{cell_position, Action, Result};
+
+/// Width of a modal, including its border.
+const MODAL_WIDTH: u16 = 50;
+
+/// A button at the bottom of a modal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Button {
+    pub label: String,
+    /// Dispatched when the button is pressed, after the modal is closed
+    pub action: Option<Action>,
+}
+
+impl Button {
+    pub fn new(label: &str, action: Option<Action>) -> Self {
+        Self {
+            label: label.to_string(),
+            action,
+        }
+    }
+}
+
+/// A single line input of a form.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Field {
+    pub label: String,
+    /// Initial value
+    pub value: String,
+    /// Hide the value, e.g. for passwords
+    pub masked: bool,
+}
+
+impl Field {
+    pub fn new(label: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Content of a modal, shown with `Action::ShowModal`.
+///
+/// A button with `Action::SubmitForm(name, _)` gets the values of the fields filled in when
+/// it is pressed.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Modal {
+    pub title: String,
+    pub body: String,
+    pub fields: Vec<Field>,
+    pub buttons: Vec<Button>,
+}
+
+impl Modal {
+    pub fn new(title: &str, body: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            body: body.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Message with a single button to close it.
+    pub fn info(title: &str, body: &str) -> Self {
+        Self::new(title, body).button("OK", None)
+    }
+
+    /// Ask before doing something that can't be undone.
+    pub fn confirm(title: &str, body: &str, action: Action) -> Self {
+        Self::new(title, body)
+            .button("Confirm", Some(action))
+            .button("Cancel", None)
+    }
+
+    pub fn button(mut self, label: &str, action: Option<Action>) -> Self {
+        self.buttons.push(Button::new(label, action));
+        self
+    }
+
+    pub fn field(mut self, field: Field) -> Self {
+        self.fields.push(field);
+        self
+    }
+}
+
+/// A modal on the stack, with its focus and layout
+struct Open {
+    modal: Modal,
+    /// One input per field of the modal
+    inputs: Vec<TextInput>,
+    /// Focused field or button, fields come first
+    focus: usize,
+    /// Button areas from the last draw, for mouse clicks
+    button_areas: Vec<Rect>,
+    /// Field areas from the last draw, for mouse clicks
+    field_areas: Vec<Rect>,
+}
+
+impl Open {
+    fn new(modal: Modal) -> Self {
+        let inputs = modal
+            .fields
+            .iter()
+            .map(|field| {
+                let mut input = TextInput::new(&field.label);
+                if field.masked {
+                    input = input.masked();
+                }
+                input.set_value(&field.value);
+                input
+            })
+            .collect();
+        let mut open = Self {
+            modal,
+            inputs,
+            focus: 0,
+            button_areas: Vec::new(),
+            field_areas: Vec::new(),
+        };
+        open.set_focus(0);
+        open
+    }
+
+    fn set_focus(&mut self, focus: usize) {
+        self.focus = focus;
+        for (index, input) in self.inputs.iter_mut().enumerate() {
+            input.set_focused(index == focus);
+        }
+    }
+
+    fn focusable(&self) -> usize {
+        self.modal.fields.len() + self.modal.buttons.len()
+    }
+
+    fn focused_input(&mut self) -> Option<&mut TextInput> {
+        self.inputs.get_mut(self.focus)
+    }
+
+    fn focused_button(&self) -> Option<usize> {
+        self.focus.checked_sub(self.inputs.len())
+    }
+
+    fn focus_next(&mut self) {
+        self.set_focus((self.focus + 1) % self.focusable().max(1));
+    }
+
+    fn focus_previous(&mut self) {
+        let count = self.focusable().max(1);
+        self.set_focus((self.focus + count - 1) % count);
+    }
+}
+
+/// Stack of modal dialogs drawn above the current page.
+///
+/// While a modal is open it takes all keyboard and mouse input. `Action::Popup(title, body)`
+/// shows a message, `Action::ShowModal` anything else, `Action::ClosePopup` closes the top
+/// modal without dispatching anything.
+pub struct Popup {
+    stack: Vec<Open>,
+    // Action Handler
+    tx: Option<UnboundedSender<Action>>,
+    /// Terminal area, for mapping mouse positions to cells
+    area: Rect,
+}
+
+impl Default for Popup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Popup {
+    pub fn new() -> Self {
+        Self {
+            stack: Vec::new(),
+            tx: None,
+            area: Rect::default(),
+        }
+    }
+
+    fn send(&self, action: Action) {
+        if let Some(tx) = self.tx.as_ref() {
+            tx.send(action).ok();
+        }
+    }
+
+    /// Close the top modal and dispatch the action of its `index`th button.
+    fn press(&mut self, index: usize) {
+        let Some(open) = self.stack.pop() else {
+            return;
+        };
+        let action = open
+            .modal
+            .buttons
+            .get(index)
+            .and_then(|button| button.action.clone());
+        match action {
+            Some(Action::SubmitForm(name, _)) => {
+                let values = open
+                    .inputs
+                    .iter()
+                    .map(|input| input.value().to_string())
+                    .collect();
+                self.send(Action::SubmitForm(name, values));
+            }
+            Some(action) => self.send(action),
+            None => {}
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        let Some(open) = self.stack.last_mut() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => {
+                self.stack.pop();
+            }
+            KeyCode::Tab if key.shift => open.focus_previous(),
+            KeyCode::Tab | KeyCode::Down => open.focus_next(),
+            KeyCode::Up => open.focus_previous(),
+            KeyCode::Left | KeyCode::Right if open.focused_button().is_some() => {
+                if key.code == KeyCode::Left {
+                    open.focus_previous()
+                } else {
+                    open.focus_next()
+                }
+            }
+            KeyCode::Enter => match open.focused_button() {
+                Some(index) => self.press(index),
+                // Enter in the last field submits with the first button
+                None if open.focus + 1 == open.inputs.len() => self.press(0),
+                None => open.focus_next(),
+            },
+            _ => match open.focused_input() {
+                Some(input) => {
+                    input.handle_key(&key);
+                }
+                None => {
+                    let KeyCode::Char(c) = key.code else {
+                        return;
+                    };
+                    // Buttons can be pressed with their first letter
+                    let index = open.modal.buttons.iter().position(|button| {
+                        button
+                            .label
+                            .chars()
+                            .next()
+                            .is_some_and(|first| first.eq_ignore_ascii_case(&c))
+                    });
+                    if let Some(index) = index {
+                        self.press(index);
+                    }
+                }
+            },
+        }
+    }
+}
+
+impl Component for Popup {
+    fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
+        self.tx = Some(tx);
+        Ok(())
+    }
+
+    fn on_resize(&mut self, area: Rect) -> Result<()> {
+        self.area = area;
+        Ok(())
+    }
+
+    fn captures_input(&self) -> bool {
+        !self.stack.is_empty()
+    }
+
+    fn handle_mouse(&mut self, mouse: MouseEvent) -> Option<bool> {
+        let open = self.stack.last_mut()?;
+        // Nothing under an open modal can be clicked
+        if mouse.event != MouseEventKind::Pressed || mouse.button != MouseButton::Left {
+            return Some(true);
+        }
+        let position = cell_position(&mouse, self.area);
+        let fields = open.inputs.len();
+        if let Some(index) = open.field_areas.iter().position(|a| a.contains(position)) {
+            open.set_focus(index);
+        } else if let Some(index) = open.button_areas.iter().position(|a| a.contains(position)) {
+            open.set_focus(fields + index);
+            self.press(index);
+        }
+        Some(true)
+    }
+
+    fn copy_text(&mut self) -> Option<String> {
+        let open = self.stack.last_mut()?;
+        match open.focused_input() {
+            Some(input) => input.copy_text(),
+            None => Some(open.modal.body.clone()),
+        }
+    }
+
+    fn handle_events(&mut self, key: KeyEvent) -> Option<bool> {
+        if self.stack.is_empty() {
+            return None;
+        }
+        self.handle_key(key);
+        Some(true)
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::Popup(title, body) => self.stack.push(Open::new(Modal::info(&title, &body))),
+            Action::ShowModal(modal) => self.stack.push(Open::new(modal)),
+            Action::ClosePopup => {
+                self.stack.pop();
+            }
+            Action::Paste(text) => {
+                if let Some(input) = self.stack.last_mut().and_then(Open::focused_input) {
+                    input.insert_str(&text);
+                }
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let top = self.stack.len().saturating_sub(1);
+        for (depth, open) in self.stack.iter_mut().enumerate() {
+            draw_modal(frame, open, depth == top);
+        }
+    }
+}
+
+fn draw_modal(frame: &mut Frame, open: &mut Open, focused: bool) {
+    // Only the top modal shows a cursor
+    match focused {
+        true => open.set_focus(open.focus),
+        false => open.inputs.iter_mut().for_each(|i| i.set_focused(false)),
+    }
+    let modal = &open.modal;
+    let width = MODAL_WIDTH.min(frame.area().width);
+    let body_lines = modal
+        .body
+        .lines()
+        .map(|line| {
+            Line::from(line)
+                .width()
+                .div_ceil(width.saturating_sub(4).max(1) as usize)
+                .max(1)
+        })
+        .sum::<usize>() as u16;
+    let height = 2 + body_lines + 1 + 3 * modal.fields.len() as u16 + 1;
+    let [area] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(frame.area());
+    let [area] = Layout::horizontal([Constraint::Length(width)])
+        .flex(Flex::Center)
+        .areas(area);
+
+    let border = if focused {
+        Color::Yellow
+    } else {
+        Color::DarkGray
+    };
+    let block = Block::bordered()
+        .title_top(Line::from(modal.title.as_str()).bold().centered())
+        .border_style(Style::default().fg(border))
+        .style(Style::default().bg(Color::Black).fg(Color::White));
+    let inner = block.inner(area).inner(Margin::new(1, 0));
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+
+    let mut constraints = vec![Constraint::Length(body_lines), Constraint::Length(1)];
+    constraints.extend(modal.fields.iter().map(|_| Constraint::Length(3)));
+    constraints.push(Constraint::Length(1));
+    let rows = Layout::vertical(constraints).split(inner);
+    frame.render_widget(
+        Paragraph::new(modal.body.as_str()).wrap(Wrap { trim: false }),
+        rows[0],
+    );
+
+    open.field_areas = rows[2..rows.len() - 1].to_vec();
+    for (input, area) in open.inputs.iter_mut().zip(&open.field_areas) {
+        input.draw(frame, *area);
+    }
+
+    let labels: Vec<String> = modal
+        .buttons
+        .iter()
+        .map(|button| format!("[ {} ]", button.label))
+        .collect();
+    open.button_areas = Layout::horizontal(
+        labels
+            .iter()
+            .map(|label| Constraint::Length(label.chars().count() as u16)),
+    )
+    .spacing(2)
+    .flex(Flex::Center)
+    .split(rows[rows.len() - 1])
+    .to_vec();
+    for (index, (label, area)) in labels.iter().zip(&open.button_areas).enumerate() {
+        let selected = focused && open.focused_button() == Some(index);
+        let style = if selected {
+            Style::default().fg(Color::Black).bg(Color::Yellow).bold()
+        } else {
+            Style::default()
+        };
+        frame.render_widget(Line::styled(label.as_str(), style), *area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::key;
+    use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+    fn popup() -> (Popup, UnboundedReceiver<Action>) {
+        let (tx, rx) = unbounded_channel();
+        let mut popup = Popup::new();
+        popup.register_action_handler(tx).unwrap();
+        (popup, rx)
+    }
+
+    #[test]
+    fn confirm_dispatches_its_action() {
+        let (mut popup, mut rx) = popup();
+        assert!(!popup.captures_input());
+        let modal = Modal::confirm("Log out", "Really?", Action::LoggedOut);
+        popup.update(Action::ShowModal(modal)).unwrap();
+        assert!(popup.captures_input());
+        assert_eq!(popup.handle_events(key(KeyCode::Enter)), Some(true));
+        assert_eq!(rx.try_recv(), Ok(Action::LoggedOut));
+        assert!(!popup.captures_input());
+    }
+
+    #[test]
+    fn cancel_dispatches_nothing() {
+        let (mut popup, mut rx) = popup();
+        let modal = Modal::confirm("Log out", "Really?", Action::LoggedOut);
+        popup.update(Action::ShowModal(modal.clone())).unwrap();
+        popup.handle_events(key(KeyCode::Right));
+        popup.handle_events(key(KeyCode::Enter));
+        popup.update(Action::ShowModal(modal)).unwrap();
+        popup.handle_events(key(KeyCode::Esc));
+        assert!(rx.try_recv().is_err());
+        assert!(!popup.captures_input());
+    }
+
+    #[test]
+    fn modals_stack() {
+        let (mut popup, mut rx) = popup();
+        popup
+            .update(Action::Popup("First".into(), String::new()))
+            .unwrap();
+        let modal = Modal::confirm("Second", "", Action::ClearHistory);
+        popup.update(Action::ShowModal(modal)).unwrap();
+        popup.handle_events(key(KeyCode::Char('c')));
+        assert_eq!(rx.try_recv(), Ok(Action::ClearHistory));
+        assert_eq!(popup.stack.len(), 1);
+        popup.update(Action::ClosePopup).unwrap();
+        assert!(!popup.captures_input());
+    }
+
+    #[test]
+    fn forms_submit_their_values() {
+        let (mut popup, mut rx) = popup();
+        let modal = Modal::new("Sign in", "")
+            .field(Field::new("User"))
+            .field(Field {
+                masked: true,
+                ..Field::new("Password")
+            })
+            .button("Submit", Some(Action::SubmitForm("sign-in".into(), vec![])));
+        popup.update(Action::ShowModal(modal)).unwrap();
+        for code in [KeyCode::Char('a'), KeyCode::Tab, KeyCode::Char('b')] {
+            popup.handle_events(key(code));
+        }
+        popup.handle_events(key(KeyCode::Enter));
+        assert_eq!(
+            rx.try_recv(),
+            Ok(Action::SubmitForm(
+                "sign-in".into(),
+                vec!["a".into(), "b".into()]
+            ))
+        );
+    }
+}
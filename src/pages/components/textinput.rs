@@ -1,154 +1,238 @@
-use crate::app::Page;
-use crate::utils::{Action, Result};
-use crate::APP_NAME;
-use ratatui::widgets::Wrap;
-use ratzilla::event::{KeyCode, MouseButton, MouseEvent};
-use ratzilla::event::{KeyEvent, MouseEventKind};
-use ratzilla::ratatui::layout::{Constraint, Layout, Position};
-use ratzilla::ratatui::prelude::*;
-use ratzilla::ratatui::style::{Style, Stylize};
-use ratzilla::ratatui::text::{Line, Text};
+use ratzilla::event::{KeyCode, KeyEvent};
+use ratzilla::ratatui::layout::{Position, Rect};
+use ratzilla::ratatui::style::{Color, Style, Stylize};
+use ratzilla::ratatui::text::Line;
+use ratzilla::ratatui::widgets::{Block, Paragraph};
 use ratzilla::ratatui::Frame;
-use ratzilla::ratatui::{
-    style::Color,
-    widgets::Clear,
-    widgets::{Block, Paragraph},
-};
-use tachyonfx::fx::RepeatMode;
-use tachyonfx::{
-    fx, CenteredShrink, Duration, Effect, EffectRenderer, EffectTimer, Interpolation, Motion,
-};
-use tokio::sync::mpsc::UnboundedSender;
-
-use crate::pages::Component;
-
-pub enum InputMode {
-    Normal,
-    Editing,
-}
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Checks the value of an input, returning the message to show when it is invalid.
+pub type Validator = Box<dyn Fn(&str) -> std::result::Result<(), String>>;
 
-/// App holds the state of the application
+/// Single line text input shared by the pages.
+///
+/// The widget only edits its value, keys like Enter and Esc are left to the owner so each
+/// page can decide what submitting means.
+///
+/// The cursor moves over grapheme clusters, so emoji and combining marks are edited as one
+/// character, and is placed by display width, so wide (e.g. CJK) characters take two
+/// cells. Text wider than the box scrolls horizontally to keep the cursor visible.
 pub struct TextInput {
+    /// Title of the border
+    label: String,
+    /// Shown greyed out while the input is empty
+    placeholder: String,
     /// Current value of the input box
     input: String,
-    /// Position of cursor in the editor area.
+    /// Position of cursor in the editor area, in graphemes
     character_index: usize,
-    /// Current input mode
-    input_mode: InputMode,
-    /// History of recorded messages
-    messages: Vec<String>,
-    // Action Handler
-    tx: Option<UnboundedSender<Action>>,
-    // Effect
-    intro_effect: Effect,
+    /// Columns of the value scrolled out on the left
+    scroll: usize,
+    /// Show `*` instead of the value, e.g. for passwords
+    masked: bool,
+    /// Maximum number of graphemes
+    max_length: Option<usize>,
+    validator: Option<Validator>,
+    /// Message of the last failed validation, cleared on the next edit
+    error: Option<String>,
+    /// Whether keys go to this input, which shows the cursor
+    focused: bool,
 }
 
-impl Component for TextInput {
-    fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
-        self.tx = Some(tx);
-        Ok(())
-    }
-    fn handle_mouse(&mut self, mouse: MouseEvent) -> Result<Option<Action>> {
-        match mouse.button {
-            MouseButton::Left => match mouse.event {
-                MouseEventKind::Pressed => {
-                    // Set focus to input box on click
-                    self.input_mode = InputMode::Editing;
-                }
-
-                _ => {}
-            },
-            _ => {}
-        };
-        Ok(None)
-    }
-    fn handle_events(&mut self, key_event: KeyEvent) -> Option<bool> {
-        match self.input_mode {
-            InputMode::Normal => {
-                if let KeyCode::Char('i') = key_event.code {
-                    self.tx
-                        .as_ref()
-                        .unwrap()
-                        .send(Action::ChangePage(Page::Home))
-                        .ok();
-                }
-                if let KeyCode::Char('e') = key_event.code {
-                    self.input_mode = InputMode::Editing;
-                }
-                None
-            }
-            InputMode::Editing => {
-                match key_event.code {
-                    KeyCode::Enter => self.submit_message(),
-                    KeyCode::Char(to_insert) => self.enter_char(to_insert),
-                    KeyCode::Backspace => self.delete_char(),
-                    KeyCode::Left => self.move_cursor_left(),
-                    KeyCode::Right => self.move_cursor_right(),
-                    KeyCode::Esc => self.input_mode = InputMode::Normal,
-                    _ => {}
-                }
-                Some(true)
-            }
-        }
-    }
-
-    fn draw(&mut self, frame: &mut Frame) {
-        Clear.render(frame.area(), frame.buffer_mut());
-        let area = frame.area().inner_centered(40, 25);
-        let input = Paragraph::new(self.input.as_str())
-            .style(match self.input_mode {
-                InputMode::Normal => Style::default(),
-                InputMode::Editing => Style::default().fg(Color::Yellow),
-            })
-            .block(Block::bordered().title("Email"));
-        frame.render_widget(input, area);
-        match self.input_mode {
-            // Hide the cursor. `Frame` does this by default, so we don't need to do anything here
-            InputMode::Normal => {}
-
-            // Make the cursor visible and ask ratatui to put it at the specified coordinates after
-            // rendering
-            InputMode::Editing => frame.set_cursor_position(Position::new(
-                // Draw the cursor at the current position in the input field.
-                // This position can be controlled via the left and right arrow key
-                area.x + self.character_index as u16 + 1,
-                // Move one line down, from the border to the input line
-                area.y + 1,
-            )),
-        }
-        frame.render_effect(&mut self.intro_effect, area, Duration::from_millis(40));
+impl Default for TextInput {
+    fn default() -> Self {
+        Self::new("")
     }
 }
 
 impl TextInput {
-    pub fn new() -> Self {
+    pub fn new(label: &str) -> Self {
         Self {
+            label: label.to_string(),
+            placeholder: String::new(),
             input: String::new(),
-            input_mode: InputMode::Normal,
-            messages: Vec::new(),
             character_index: 0,
-            tx: None,
-            intro_effect: fx::sequence(&[
-                // fx::ping_pong(fx::sweep_in(
-                //     Motion::LeftToRight,
-                //     10,
-                //     0,
-                //     Color::Black,
-                //     EffectTimer::from_ms(3000, Interpolation::QuadIn),
-                // )),
-                fx::coalesce((3000, Interpolation::SineOut)),
-                fx::sleep(1000),
-                fx::repeat(
-                    fx::hsl_shift(
-                        Some([120.0, 25.0, 25.0]),
-                        None,
-                        (5000, Interpolation::Linear),
-                    ),
-                    RepeatMode::Forever,
-                ),
-            ]),
+            scroll: 0,
+            masked: false,
+            max_length: None,
+            validator: None,
+            error: None,
+            focused: false,
+        }
+    }
+
+    pub fn placeholder(mut self, placeholder: &str) -> Self {
+        self.placeholder = placeholder.to_string();
+        self
+    }
+
+    pub fn masked(mut self) -> Self {
+        self.masked = true;
+        self
+    }
+
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.max_length = Some(max_length);
+        self
+    }
+
+    pub fn validator(
+        mut self,
+        validator: impl Fn(&str) -> std::result::Result<(), String> + 'static,
+    ) -> Self {
+        self.validator = Some(Box::new(validator));
+        self
+    }
+
+    pub fn value(&self) -> &str {
+        &self.input
+    }
+
+    /// Replace the value, moving the cursor to the end.
+    pub fn set_value(&mut self, value: &str) {
+        self.input.clear();
+        self.character_index = 0;
+        self.error = None;
+        self.insert_str(value);
+    }
+
+    pub fn clear(&mut self) {
+        self.set_value("");
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    /// Value to copy to the clipboard, nothing for empty or masked inputs.
+    pub fn copy_text(&self) -> Option<String> {
+        (!self.masked && !self.input.is_empty()).then(|| self.input.clone())
+    }
+
+    /// Run the validator, showing its message under the input when the value is rejected.
+    pub fn validate(&mut self) -> std::result::Result<(), String> {
+        let result = match &self.validator {
+            Some(validator) => validator(&self.input),
+            None => Ok(()),
+        };
+        self.error = result.as_ref().err().cloned();
+        result
+    }
+
+    /// Edit the value with `key`, returning whether the key was used.
+    pub fn handle_key(&mut self, key: &KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Char('w') if key.ctrl => self.delete_word(),
+            KeyCode::Char('u') if key.ctrl => self.delete_to_start(),
+            KeyCode::Char(_) if key.ctrl || key.alt => return false,
+            KeyCode::Char(to_insert) => self.enter_char(to_insert),
+            KeyCode::Backspace => self.delete_char(),
+            KeyCode::Delete => self.delete_char_forward(),
+            KeyCode::Left if key.ctrl => self.move_word_left(),
+            KeyCode::Right if key.ctrl => self.move_word_right(),
+            KeyCode::Left => self.move_cursor_left(),
+            KeyCode::Right => self.move_cursor_right(),
+            KeyCode::Home => self.character_index = 0,
+            KeyCode::End => self.character_index = self.len(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Insert text at the cursor, e.g. from a paste, dropping line breaks.
+    pub fn insert_str(&mut self, text: &str) {
+        let text: String = text.chars().filter(|c| !c.is_control()).collect();
+        for grapheme in text.graphemes(true) {
+            if !self.insert_grapheme(grapheme) {
+                break;
+            }
+        }
+    }
+
+    /// Draw the input into `area`, which should be 3 rows high for the border.
+    pub fn draw(&mut self, frame: &mut Frame, area: Rect) {
+        let (visible, cursor) = self.visible(area.width.saturating_sub(2) as usize);
+        let border = match (&self.error, self.focused) {
+            (Some(_), _) => Style::default().fg(Color::Red),
+            (None, true) => Style::default().fg(Color::Yellow),
+            (None, false) => Style::default(),
+        };
+        let mut block = Block::bordered()
+            .title(self.label.as_str())
+            .border_style(border);
+        if let Some(error) = &self.error {
+            block = block.title_bottom(Line::from(error.as_str()).red());
         }
+        let text = if self.input.is_empty() {
+            Line::from(self.placeholder.as_str()).dark_gray()
+        } else {
+            Line::from(visible)
+        };
+        let style = if self.focused {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        };
+        frame.render_widget(Paragraph::new(text).style(style).block(block), area);
+        if self.focused {
+            // Move one line down, from the border to the input line
+            frame.set_cursor_position(Position::new(area.x + 1 + cursor as u16, area.y + 1));
+        }
+    }
+
+    /// Graphemes as they are displayed, masked or not.
+    fn displayed(&self) -> Vec<&str> {
+        if self.masked {
+            vec!["*"; self.len()]
+        } else {
+            self.input.graphemes(true).collect()
+        }
+    }
+
+    /// Scroll so the cursor fits into `width` columns, returning the visible part of the
+    /// value and the column of the cursor within it.
+    fn visible(&mut self, width: usize) -> (String, usize) {
+        let graphemes = self.displayed();
+        let cursor: usize = graphemes[..self.character_index]
+            .iter()
+            .map(|g| g.width())
+            .sum();
+        // Keep a column free for the cursor at the end of the text
+        let width = width.max(1);
+        let mut scroll = self.scroll;
+        if cursor < scroll {
+            scroll = cursor;
+        } else if cursor >= scroll + width {
+            scroll = cursor + 1 - width;
+        }
+        let mut visible = String::new();
+        let mut column = 0;
+        for grapheme in graphemes {
+            let start = column;
+            column += grapheme.width();
+            if column > scroll + width {
+                break;
+            }
+            if start >= scroll {
+                visible.push_str(grapheme);
+            } else if column > scroll {
+                // Wide grapheme cut by the left edge
+                visible.push_str(&" ".repeat(column - scroll));
+            }
+        }
+        self.scroll = scroll;
+        (visible, cursor - scroll)
+    }
+
+    fn len(&self) -> usize {
+        self.input.graphemes(true).count()
     }
+
     fn move_cursor_left(&mut self) {
         let cursor_moved_left = self.character_index.saturating_sub(1);
         self.character_index = self.clamp_cursor(cursor_moved_left);
@@ -159,63 +243,250 @@ impl TextInput {
         self.character_index = self.clamp_cursor(cursor_moved_right);
     }
 
+    /// Whether each grapheme of the value is whitespace, for word motions.
+    fn spaces(&self) -> Vec<bool> {
+        self.input
+            .graphemes(true)
+            .map(|g| g.chars().all(char::is_whitespace))
+            .collect()
+    }
+
+    /// Start of the word before the cursor, skipping whitespace first.
+    fn previous_word_start(&self) -> usize {
+        let spaces = self.spaces();
+        let mut index = self.character_index;
+        while index > 0 && spaces[index - 1] {
+            index -= 1;
+        }
+        while index > 0 && !spaces[index - 1] {
+            index -= 1;
+        }
+        index
+    }
+
+    /// End of the word after the cursor, skipping whitespace first.
+    fn next_word_end(&self) -> usize {
+        let spaces = self.spaces();
+        let mut index = self.character_index;
+        while index < spaces.len() && spaces[index] {
+            index += 1;
+        }
+        while index < spaces.len() && !spaces[index] {
+            index += 1;
+        }
+        index
+    }
+
+    fn move_word_left(&mut self) {
+        self.character_index = self.previous_word_start();
+    }
+
+    fn move_word_right(&mut self) {
+        self.character_index = self.next_word_end();
+    }
+
     fn enter_char(&mut self, new_char: char) {
-        let index = self.byte_index();
-        self.input.insert(index, new_char);
-        self.move_cursor_right();
+        self.insert_grapheme(new_char.encode_utf8(&mut [0; 4]));
+    }
+
+    /// Insert at the cursor, returning whether there was room for it.
+    ///
+    /// A combining mark joins the grapheme before the cursor instead of adding one.
+    fn insert_grapheme(&mut self, grapheme: &str) -> bool {
+        let before = self.len();
+        if self.max_length.is_some_and(|max| before >= max) && !self.joins_previous(grapheme) {
+            return false;
+        }
+        let index = self.byte_index(self.character_index);
+        self.input.insert_str(index, grapheme);
+        self.error = None;
+        // Counted rather than added up, as the insert can merge graphemes, e.g. a ZWJ
+        // between two emoji leaves fewer of them than before
+        self.character_index = self.input[..index + grapheme.len()].graphemes(true).count();
+        true
+    }
+
+    /// Whether `grapheme` merges with the grapheme before the cursor when inserted.
+    fn joins_previous(&self, grapheme: &str) -> bool {
+        let index = self.byte_index(self.character_index);
+        let joined = format!("{}{}", &self.input[..index], grapheme);
+        joined.graphemes(true).count() == self.character_index
     }
 
-    /// Returns the byte index based on the character position.
+    /// Returns the byte index based on the grapheme position.
     ///
-    /// Since each character in a string can contain multiple bytes, it's necessary to calculate
-    /// the byte index based on the index of the character.
-    fn byte_index(&self) -> usize {
+    /// Since each grapheme in a string can contain multiple chars and bytes, it's necessary
+    /// to calculate the byte index based on the index of the grapheme.
+    fn byte_index(&self, character_index: usize) -> usize {
         self.input
-            .char_indices()
+            .grapheme_indices(true)
             .map(|(i, _)| i)
-            .nth(self.character_index)
+            .nth(character_index)
             .unwrap_or(self.input.len())
     }
 
+    /// Remove the characters in `start..end`, leaving the cursor at `start`.
+    fn delete_range(&mut self, start: usize, end: usize) {
+        if start >= end {
+            return;
+        }
+        let range = self.byte_index(start)..self.byte_index(end);
+        self.input.replace_range(range, "");
+        self.character_index = start;
+        self.error = None;
+    }
+
     fn delete_char(&mut self) {
-        let is_not_cursor_leftmost = self.character_index != 0;
-        if is_not_cursor_leftmost {
-            // Method "remove" is not used on the saved text for deleting the selected char.
-            // Reason: Using remove on String works on bytes instead of the chars.
-            // Using remove would require special care because of char boundaries.
+        if self.character_index != 0 {
+            self.delete_range(self.character_index - 1, self.character_index);
+        }
+    }
 
-            let current_index = self.character_index;
-            let from_left_to_current_index = current_index - 1;
+    fn delete_char_forward(&mut self) {
+        if self.character_index < self.len() {
+            self.delete_range(self.character_index, self.character_index + 1);
+        }
+    }
 
-            // Getting all characters before the selected character.
-            let before_char_to_delete = self.input.chars().take(from_left_to_current_index);
-            // Getting all characters after selected character.
-            let after_char_to_delete = self.input.chars().skip(current_index);
+    fn delete_word(&mut self) {
+        self.delete_range(self.previous_word_start(), self.character_index);
+    }
 
-            // Put all characters together except the selected one.
-            // By leaving the selected one out, it is forgotten and therefore deleted.
-            self.input = before_char_to_delete.chain(after_char_to_delete).collect();
-            self.move_cursor_left();
-        }
+    fn delete_to_start(&mut self) {
+        self.delete_range(0, self.character_index);
     }
 
     fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
-        new_cursor_pos.clamp(0, self.input.chars().count())
+        new_cursor_pos.clamp(0, self.len())
     }
+}
 
-    fn reset_cursor(&mut self) {
-        self.character_index = 0;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{ctrl, key};
+
+    fn typed(text: &str) -> TextInput {
+        let mut input = TextInput::new("Test");
+        for c in text.chars() {
+            input.handle_key(&key(KeyCode::Char(c)));
+        }
+        input
     }
 
-    fn submit_message(&mut self) {
-        self.messages.push(self.input.clone());
-        self.input.clear();
-        self.reset_cursor();
-        self.input_mode = InputMode::Normal;
-        self.tx
-            .as_ref()
-            .unwrap()
-            .send(Action::SubmitEmail(self.input.clone()))
-            .ok();
+    #[test]
+    fn edits_multibyte_text() {
+        let mut input = typed("héllo");
+        input.handle_key(&key(KeyCode::Left));
+        input.handle_key(&key(KeyCode::Backspace));
+        input.handle_key(&key(KeyCode::Home));
+        input.handle_key(&key(KeyCode::Delete));
+        assert_eq!(input.value(), "élo");
+        input.handle_key(&key(KeyCode::End));
+        input.handle_key(&key(KeyCode::Char('!')));
+        assert_eq!(input.value(), "élo!");
+    }
+
+    #[test]
+    fn word_motions_and_kills() {
+        let mut input = typed("one two  three");
+        input.handle_key(&ctrl(KeyCode::Left));
+        assert_eq!(input.character_index, 9);
+        input.handle_key(&ctrl(KeyCode::Left));
+        assert_eq!(input.character_index, 4);
+        input.handle_key(&ctrl(KeyCode::Right));
+        assert_eq!(input.character_index, 7);
+        input.handle_key(&ctrl(KeyCode::Char('w')));
+        assert_eq!(input.value(), "one   three");
+        input.handle_key(&ctrl(KeyCode::Char('u')));
+        assert_eq!(input.value(), "  three");
+        assert_eq!(input.character_index, 0);
+    }
+
+    #[test]
+    fn max_length_and_paste() {
+        let mut input = TextInput::new("Code").max_length(4);
+        input.insert_str("12\n345");
+        assert_eq!(input.value(), "1234");
+        assert!(!input.handle_key(&ctrl(KeyCode::Char('x'))));
+    }
+
+    #[test]
+    fn edits_whole_graphemes() {
+        // Family emoji joined with zero width joiners, and an e with a combining accent
+        let mut input = typed("a👨‍👩‍👧e\u{301}");
+        assert_eq!(input.len(), 3);
+        input.handle_key(&key(KeyCode::Left));
+        input.handle_key(&key(KeyCode::Backspace));
+        assert_eq!(input.value(), "ae\u{301}");
+        input.handle_key(&key(KeyCode::Delete));
+        assert_eq!(input.value(), "a");
+    }
+
+    #[test]
+    fn combining_marks_ignore_max_length() {
+        let mut input = TextInput::new("Name").max_length(1);
+        input.insert_str("e\u{301}x");
+        assert_eq!(input.value(), "e\u{301}");
+    }
+
+    #[test]
+    fn cursor_uses_display_width() {
+        let mut input = typed("日本");
+        assert_eq!(input.visible(10), ("日本".to_string(), 4));
+        input.handle_key(&key(KeyCode::Left));
+        assert_eq!(input.visible(10).1, 2);
+    }
+
+    #[test]
+    fn scrolls_to_keep_the_cursor_visible() {
+        let mut input = typed("abcdefgh");
+        assert_eq!(input.visible(4), ("fgh".to_string(), 3));
+        input.handle_key(&key(KeyCode::Home));
+        assert_eq!(input.visible(4), ("abcd".to_string(), 0));
+
+        // A wide character cut by the left edge is replaced by spaces
+        let mut input = typed("a日bc");
+        assert_eq!(input.visible(4), (" bc".to_string(), 3));
+    }
+
+    #[test]
+    fn inserts_that_merge_graphemes_keep_the_cursor_after_them() {
+        let mut input = typed("👨👩");
+        input.handle_key(&key(KeyCode::Left));
+        input.insert_str("\u{200d}");
+        assert_eq!(input.value(), "👨\u{200d}👩");
+        assert_eq!(input.character_index, 1);
+        assert_eq!(input.visible(10), ("👨\u{200d}👩".to_string(), 2));
+
+        let mut input = typed("ex");
+        input.handle_key(&key(KeyCode::Left));
+        input.handle_key(&key(KeyCode::Char('\u{301}')));
+        assert_eq!(input.value(), "e\u{301}x");
+        assert_eq!(input.character_index, 1);
+        input.handle_key(&key(KeyCode::Backspace));
+        assert_eq!(input.value(), "x");
+    }
+
+    #[test]
+    fn masked_input_shows_one_star_per_grapheme() {
+        let mut input = typed("日👨‍👩‍👧").masked();
+        assert_eq!(input.visible(10), ("**".to_string(), 2));
+    }
+
+    #[test]
+    fn validator_errors_clear_on_edit() {
+        let mut input = typed("a").validator(|value| {
+            if value.len() > 1 {
+                Ok(())
+            } else {
+                Err("Too short".to_string())
+            }
+        });
+        assert_eq!(input.validate(), Err("Too short".to_string()));
+        assert!(input.error.is_some());
+        input.handle_key(&key(KeyCode::Char('b')));
+        assert!(input.error.is_none());
+        assert_eq!(input.validate(), Ok(()));
     }
 }
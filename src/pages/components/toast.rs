@@ -1,169 +1,327 @@
-use std::cell::RefCell;
-
-use layout::Offset;
-use ratatui::{style::Stylize, Frame};
 use ratzilla::{
-    event::KeyEvent,
-    ratatui::{prelude::*, widgets::Clear},
-    widgets::Hyperlink,
-};
-use tachyonfx::{
-    fx::{self, RepeatMode},
-    CenteredShrink, Duration, Effect, EffectRenderer, Interpolation,
+    event::{MouseButton, MouseEvent, MouseEventKind},
+    ratatui::{
+        layout::Rect,
+        prelude::*,
+        widgets::{Block, Clear, Paragraph, Wrap},
+        Frame,
+    },
 };
+use tachyonfx::{fx, Effect, EffectRenderer, Interpolation, Motion};
+use tokio::sync::mpsc::UnboundedSender;
+use web_time::{Duration, Instant};
 
 use crate::pages::Component;
+use crate::utils::{cell_position, Action, Result};
+
+/// Width of a toast, including its border.
+const TOAST_WIDTH: u16 = 42;
+/// Lines of the body shown before it is cut off.
+const MAX_BODY_LINES: u16 = 4;
+/// Toasts shown at once, the others wait for a free slot.
+const MAX_VISIBLE: usize = 4;
+/// Length of the enter and exit animations in milliseconds.
+const ANIMATION_MS: u32 = 300;
+
+/// How important a toast is, which decides its color and how long it stays up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Severity {
+    #[default]
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// Get the string representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Success => "success",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Severity::Info => Color::Cyan,
+            Severity::Success => Color::Green,
+            Severity::Warning => Color::Yellow,
+            Severity::Error => Color::Red,
+        }
+    }
 
-pub struct Modal {
-    pub title: Option<String>,
-    pub content: String,
-    pub subaction: Option<Action>,
-    pub duration: Duration,
+    /// Time the toast stays up once it is visible, errors linger the longest.
+    pub fn duration(&self) -> Duration {
+        match self {
+            Severity::Info | Severity::Success => Duration::from_secs(4),
+            Severity::Warning => Duration::from_secs(6),
+            Severity::Error => Duration::from_secs(8),
+        }
+    }
 }
 
+/// A single notification in the stack
+struct Notification {
+    severity: Severity,
+    title: String,
+    body: String,
+    /// When the toast first became visible, it may wait for a slot before that
+    shown_at: Option<Instant>,
+    /// Running enter or exit animation
+    effect: Effect,
+    /// Whether the exit animation has started
+    leaving: bool,
+    /// Where the toast was drawn last, for click-to-dismiss
+    area: Rect,
+}
+
+impl Notification {
+    fn new(severity: Severity, title: String, body: String) -> Self {
+        Self {
+            severity,
+            title,
+            body,
+            shown_at: None,
+            effect: fx::slide_in(
+                Motion::RightToLeft,
+                8,
+                0,
+                Color::Reset,
+                (ANIMATION_MS, Interpolation::QuadOut),
+            ),
+            leaving: false,
+            area: Rect::default(),
+        }
+    }
+
+    /// Start the exit animation, the toast is removed once it is done.
+    fn dismiss(&mut self) {
+        if !self.leaving {
+            self.leaving = true;
+            self.effect = fx::slide_out(
+                Motion::LeftToRight,
+                8,
+                0,
+                Color::Reset,
+                (ANIMATION_MS, Interpolation::QuadIn),
+            );
+        }
+    }
+
+    fn is_expired(&self, now: Instant) -> bool {
+        self.shown_at
+            .is_some_and(|shown_at| now.duration_since(shown_at) >= self.severity.duration())
+    }
+
+    /// Height of the toast for the given width, including its border.
+    fn height(&self, width: u16) -> u16 {
+        let inner = width.saturating_sub(2).max(1) as usize;
+        let lines: usize = self
+            .body
+            .lines()
+            .map(|line| Line::from(line).width().div_ceil(inner).max(1))
+            .sum();
+        (lines as u16).clamp(1, MAX_BODY_LINES) + 2
+    }
+}
+
+/// Notifications stacked in the top right corner, on top of every page.
+///
+/// Toasts are added with `Action::Toast(title, body)` or `Action::Notify` for a severity
+/// other than info, and go away on their own, on `Action::CloseToast` (newest first, sent
+/// for an Esc no page or binding uses) or when clicked.
 pub struct Toast {
-    pub toasts: Vec<Modal>,
-    pub menu_index: usize,
-    pub action_tx: Option<UnboundedSender<Action>>,
-    render_start_time: Instant,
-    render_frames: u32,
+    /// Toasts, newest first
+    toasts: Vec<Notification>,
+    // Action Handler
+    tx: Option<UnboundedSender<Action>>,
+    /// Terminal area, for mapping mouse positions to cells
     area: Rect,
-    content_area: Rect,
+    /// Time of the last draw, animations advance by the real time in between
+    last_frame: Option<Instant>,
+}
+
+impl Default for Toast {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Toast {
     pub fn new() -> Self {
         Self {
-            toasts: vec![Modal {
-                title: Some("Toast".to_string()),
-                content: "This is a toast message.".to_string(),
-                subaction: None,
-                duration: Duration::from_secs(5),
-            }],
-            render_start_time: Instant::now(),
-            render_frames: 0,
-            menu_index: 0,
-            action_tx: None,
-            keymap: HashMap::new(),
+            toasts: Vec::new(),
+            tx: None,
             area: Rect::default(),
-            content_area: Rect::default(),
+            last_frame: None,
         }
     }
 
-    fn render_tick(&mut self) -> Result<()> {
-        self.render_frames += 1;
-        let now = Instant::now();
-        let elapsed = (now - self.render_start_time).as_secs_f64();
-        if elapsed >= 1.0 {
-            // Update the duration on the toasts
-            if let Some(toast) = self.toasts.first_mut() {
-                if let Some(duration) = toast.duration.checked_sub(Duration::from_secs(1)) {
-                    toast.duration = duration;
-                }
+    /// Add a toast on top of the stack.
+    pub fn push(&mut self, severity: Severity, title: String, body: String) {
+        log::info!("Toasting {} ({})", title, severity.as_str());
+        self.toasts
+            .insert(0, Notification::new(severity, title, body));
+    }
+
+    /// Start expired exit animations and drop the toasts whose exit animation is done.
+    fn tick(&mut self, now: Instant) {
+        self.toasts
+            .retain(|toast| !(toast.leaving && toast.effect.done()));
+        for toast in self.toasts.iter_mut().take(MAX_VISIBLE) {
+            if toast.shown_at.is_none() {
+                toast.shown_at = Some(now);
             }
-            self.render_start_time = now;
-            self.render_frames = 0;
-            if self.toasts.first().map_or(false, |t| t.duration.is_zero()) {
-                self.toasts.remove(0);
+            if toast.is_expired(now) {
+                toast.dismiss();
             }
         }
-        Ok(())
-    }
-
-    pub fn keymap(mut self, keymap: HashMap<KeyEvent, Action>) -> Self {
-        self.keymap = keymap;
-        self
-    }
-    /// helper function to create a centered rect using up certain percentage of the available rect `r`
-    fn toast_area(area: Rect, percent_x: u16) -> Rect {
-        let vertical = Layout::vertical([Constraint::Percentage(15)]).flex(Flex::Start);
-        let horizontal = Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::End);
-        let [area] = vertical.areas(area);
-        let [area] = horizontal.areas(area);
-        area
     }
 }
 
 impl Component for Toast {
-    fn current_mode(&self) -> InputMode {
-        InputMode::Normal
-    }
     fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
-        self.action_tx = Some(tx);
+        self.tx = Some(tx);
         Ok(())
     }
-    fn register_layout_handler(&mut self, area: Rect) -> Result<()> {
-        let area = Self::toast_area(area, 15);
-        let outer_area = area;
+
+    fn on_resize(&mut self, area: Rect) -> Result<()> {
         self.area = area;
-        // Get Areas
-        let block = Block::bordered();
-        let layout = Layout::vertical([Constraint::Min(1)]);
-        let [bottom_area] = layout.areas(outer_area);
-        let bottom_area = block.inner(bottom_area);
-        self.content_area = bottom_area;
         Ok(())
     }
 
-    fn handle_mouse_events(&mut self, mouse: MouseEvent) -> Result<Option<Action>> {
-        let tx = self.action_tx.clone().unwrap();
-
-        Ok(None)
-    }
-
-    fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
-        let action = match key.code {
-            KeyCode::Esc => Action::CloseToast,
-            KeyCode::Enter => Action::SelectOption,
-            _ => return Ok(None),
-        };
-        Ok(Some(action))
+    fn handle_mouse(&mut self, mouse: MouseEvent) -> Option<bool> {
+        if mouse.event != MouseEventKind::Pressed || mouse.button != MouseButton::Left {
+            return None;
+        }
+        let position = cell_position(&mouse, self.area);
+        let toast = self
+            .toasts
+            .iter_mut()
+            .take(MAX_VISIBLE)
+            .find(|toast| toast.area.contains(position))?;
+        toast.dismiss();
+        Some(true)
     }
 
-    fn update(&mut self, action: Action, ctx: &Ctx) -> Result<Option<Action>> {
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
         match action {
-            Action::Render => self.render_tick()?,
-            Action::Toast(title, body) => {
-                log::info!("Toasting {}", title);
-                self.toasts.push(Modal {
-                    title: Some(title),
-                    content: body,
-                    subaction: None,
-                    duration: Duration::from_secs(5),
-                });
-            }
+            Action::Toast(title, body) => self.push(Severity::Info, title, body),
+            Action::Notify(severity, title, body) => self.push(severity, title, body),
             Action::CloseToast => {
-                self.toasts.pop();
+                if let Some(toast) = self.toasts.iter_mut().find(|toast| !toast.leaving) {
+                    toast.dismiss();
+                }
             }
             _ => {}
         }
         Ok(None)
     }
 
-    fn draw(&mut self, f: &mut Frame<'_>) -> Result<()> {
-        if self.toasts.is_empty() {
-            return Ok(());
+    fn draw(&mut self, frame: &mut Frame) {
+        let now = Instant::now();
+        let elapsed = self
+            .last_frame
+            .map(|last_frame| now.duration_since(last_frame))
+            .unwrap_or_default();
+        self.last_frame = Some(now);
+        self.tick(now);
+        let elapsed = tachyonfx::Duration::from_millis(elapsed.as_millis() as u32);
+
+        let area = frame.area();
+        let width = TOAST_WIDTH.min(area.width);
+        let mut y = area.y + 1;
+        for toast in self.toasts.iter_mut().take(MAX_VISIBLE) {
+            let height = toast.height(width).min(area.bottom().saturating_sub(y));
+            if height < 3 {
+                toast.area = Rect::default();
+                continue;
+            }
+            toast.area = Rect::new(area.right().saturating_sub(width + 1), y, width, height);
+            y += height;
+
+            let color = toast.severity.color();
+            let block = Block::bordered()
+                .title_top(Line::from(toast.title.as_str()).bold().left_aligned())
+                .title_top(Line::from("x").right_aligned())
+                .border_style(Style::default().fg(color))
+                .style(Style::default().bg(Color::Black).fg(Color::White));
+            frame.render_widget(Clear, toast.area);
+            frame.render_widget(
+                Paragraph::new(toast.body.as_str())
+                    .wrap(Wrap { trim: false })
+                    .block(block),
+                toast.area,
+            );
+            frame.render_effect(&mut toast.effect, toast.area, elapsed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn toast(toasts: &[(Severity, &str)]) -> Toast {
+        let mut toast = Toast::new();
+        for (severity, title) in toasts {
+            toast.push(*severity, title.to_string(), String::new());
         }
-        let title = self.toasts[0]
-            .title
-            .clone()
-            .unwrap_or_else(|| "Toast".to_string());
-        let content = self.toasts[0].content.clone();
-        // Blocks for popup and button area
-        let block = Block::bordered()
-            .title_top(Line::from(title).left_aligned())
-            .style(Style::default().bg(Color::Black).fg(Color::White));
-
-        // Render the widgets
-        f.render_widget(Clear, self.area); //this clears out the background
-
-        let paragraph = Paragraph::new(content)
-            .wrap(Wrap { trim: false })
-            .bold()
-            .left_aligned();
-        f.render_widget(paragraph, self.content_area);
-        f.render_widget(block, self.area);
+        toast
+    }
 
-        Ok(())
+    #[test]
+    fn newest_toast_is_on_top() {
+        let mut toast = toast(&[(Severity::Info, "first"), (Severity::Error, "second")]);
+        assert_eq!(toast.toasts[0].title, "second");
+        toast.update(Action::CloseToast).unwrap();
+        assert!(toast.toasts[0].leaving);
+        assert!(!toast.toasts[1].leaving);
+    }
+
+    #[test]
+    fn durations_start_once_visible() {
+        let titles: Vec<_> = (0..MAX_VISIBLE + 1)
+            .map(|_| (Severity::Info, "toast"))
+            .collect();
+        let mut toast = toast(&titles);
+        let start = Instant::now();
+        toast.tick(start);
+        assert!(toast.toasts[MAX_VISIBLE].shown_at.is_none());
+
+        toast.tick(start + Severity::Info.duration());
+        assert!(toast.toasts[..MAX_VISIBLE].iter().all(|t| t.leaving));
+        assert!(!toast.toasts[MAX_VISIBLE].leaving);
+    }
+
+    #[test]
+    fn errors_stay_longer() {
+        let mut toast = toast(&[(Severity::Info, "info"), (Severity::Error, "error")]);
+        let start = Instant::now();
+        toast.tick(start);
+        toast.tick(start + Severity::Info.duration());
+        assert!(!toast.toasts[0].leaving);
+        assert!(toast.toasts[1].leaving);
+    }
+
+    #[test]
+    fn click_dismisses_the_toast_under_the_pointer() {
+        let mut toast = toast(&[(Severity::Info, "first"), (Severity::Info, "second")]);
+        toast.toasts[1].area = Rect::new(10, 5, 20, 3);
+        let click = MouseEvent {
+            button: MouseButton::Left,
+            event: MouseEventKind::Pressed,
+            x: 12,
+            y: 6,
+            ctrl: false,
+            alt: false,
+            shift: false,
+        };
+        assert_eq!(toast.handle_mouse(click), Some(true));
+        assert!(!toast.toasts[0].leaving);
+        assert!(toast.toasts[1].leaving);
     }
 }
@@ -1,25 +1,19 @@
-use crate::app::Page;
-use crate::utils::{Action, Result};
+use crate::utils::auth::is_valid_email;
+use crate::utils::{Action, FocusArea, KeySequence, Result};
 use crate::APP_NAME;
 use ratatui::widgets::Wrap;
-use ratzilla::event::{KeyCode, MouseButton, MouseEvent};
-use ratzilla::event::{KeyEvent, MouseEventKind};
-use ratzilla::ratatui::layout::{Constraint, Layout, Position};
+use ratzilla::event::{KeyCode, KeyEvent};
+use ratzilla::ratatui::layout::{Constraint, Layout};
 use ratzilla::ratatui::prelude::*;
-use ratzilla::ratatui::style::{Style, Stylize};
+use ratzilla::ratatui::style::Stylize;
 use ratzilla::ratatui::text::{Line, Text};
 use ratzilla::ratatui::Frame;
-use ratzilla::ratatui::{
-    style::Color,
-    widgets::Clear,
-    widgets::{Block, Paragraph},
-};
+use ratzilla::ratatui::{widgets::Clear, widgets::Paragraph};
 use tachyonfx::fx::RepeatMode;
-use tachyonfx::{
-    fx, CenteredShrink, Duration, Effect, EffectRenderer, EffectTimer, Interpolation, Motion,
-};
+use tachyonfx::{fx, CenteredShrink, Duration, Effect, EffectRenderer, Interpolation};
 use tokio::sync::mpsc::UnboundedSender;
 
+use crate::pages::components::TextInput;
 use crate::pages::Component;
 
 pub enum InputMode {
@@ -27,20 +21,36 @@ pub enum InputMode {
     Editing,
 }
 
+/// Outcome of the last submitted email
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Status {
+    Idle,
+    Processing,
+    Failed(String),
+    Sent,
+}
+
+/// Maximum length of an email address
+const MAX_EMAIL_LENGTH: usize = 254;
+/// Focus id of the email input
+const EMAIL: &str = "email";
+
 /// App holds the state of the application
 pub struct Login {
-    /// Current value of the input box
-    input: String,
-    /// Position of cursor in the editor area.
-    character_index: usize,
+    /// Email input
+    input: TextInput,
     /// Current input mode
     input_mode: InputMode,
-    /// History of recorded messages
-    messages: Vec<String>,
+    /// Keys that start editing, for the hint
+    edit_keys: String,
     // Action Handler
     tx: Option<UnboundedSender<Action>>,
     // Effect
     intro_effect: Effect,
+    // Progress of the login request
+    status: Status,
+    // Where the email input was last drawn
+    input_area: Rect,
 }
 
 impl Component for Login {
@@ -48,44 +58,63 @@ impl Component for Login {
         self.tx = Some(tx);
         Ok(())
     }
-    fn handle_mouse(&mut self, mouse: MouseEvent) -> Result<Option<Action>> {
-        match mouse.button {
-            MouseButton::Left => match mouse.event {
-                MouseEventKind::Pressed => {
-                    // Set focus to input box on click
-                    self.input_mode = InputMode::Editing;
-                }
-
-                _ => {}
-            },
+    fn on_enter(&mut self) -> Result<()> {
+        // Replay the intro every time the page is shown
+        self.intro_effect.reset();
+        Ok(())
+    }
+    fn on_leave(&mut self) -> Result<()> {
+        // The request is cancelled along with the page
+        if self.status == Status::Processing {
+            self.status = Status::Idle;
+        }
+        Ok(())
+    }
+    fn focus_areas(&self) -> Vec<FocusArea> {
+        vec![FocusArea::new(
+            EMAIL,
+            self.input_area,
+            matches!(self.input_mode, InputMode::Editing),
+        )]
+    }
+    fn copy_text(&mut self) -> Option<String> {
+        self.input.copy_text()
+    }
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::EnterProcessing => self.status = Status::Processing,
+            Action::Error(err) => {
+                self.status = Status::Failed(err);
+                self.set_input_mode(InputMode::Editing);
+            }
+            Action::CodeSent(_) => self.status = Status::Sent,
+            Action::LoggedIn => {
+                self.status = Status::Idle;
+                self.input.clear();
+            }
+            Action::EnterInput => self.set_input_mode(InputMode::Editing),
+            Action::Focus(id) => self.set_input_mode(match id {
+                Some(EMAIL) => InputMode::Editing,
+                _ => InputMode::Normal,
+            }),
+            Action::Paste(text) if self.input.is_focused() => self.input.insert_str(&text),
             _ => {}
-        };
+        }
         Ok(None)
     }
     fn handle_events(&mut self, key_event: KeyEvent) -> Option<bool> {
         match self.input_mode {
-            InputMode::Normal => {
-                if let KeyCode::Char('i') = key_event.code {
-                    self.tx
-                        .as_ref()
-                        .unwrap()
-                        .send(Action::ChangePage(Page::Home))
-                        .ok();
-                }
-                if let KeyCode::Char('e') = key_event.code {
-                    self.input_mode = InputMode::Editing;
-                }
-                None
-            }
+            // Bindings are in the keymap
+            InputMode::Normal => None,
             InputMode::Editing => {
                 match key_event.code {
+                    // Ignore repeated submissions while a request is in flight
+                    KeyCode::Enter if self.status == Status::Processing => {}
                     KeyCode::Enter => self.submit_message(),
-                    KeyCode::Char(to_insert) => self.enter_char(to_insert),
-                    KeyCode::Backspace => self.delete_char(),
-                    KeyCode::Left => self.move_cursor_left(),
-                    KeyCode::Right => self.move_cursor_right(),
-                    KeyCode::Esc => self.input_mode = InputMode::Normal,
-                    _ => {}
+                    KeyCode::Esc => self.set_input_mode(InputMode::Normal),
+                    _ => {
+                        self.input.handle_key(&key_event);
+                    }
                 }
                 Some(true)
             }
@@ -101,9 +130,10 @@ impl Component for Login {
             Constraint::Length(1),
             Constraint::Length(3),
             Constraint::Length(2),
+            Constraint::Length(1),
             Constraint::Min(1),
         ]);
-        let [_, main_area, text_area, input_area, help_area, _] = input.areas(area);
+        let [_, main_area, text_area, input_area, help_area, status_area, _] = input.areas(area);
         let string = APP_NAME
             .to_uppercase()
             .chars()
@@ -118,33 +148,23 @@ impl Component for Login {
 
         let text = Text::from(Line::from("Please enter your email to login."));
         frame.render_widget(Paragraph::new(text).wrap(Wrap { trim: false }), text_area);
-        let input = Paragraph::new(self.input.as_str())
-            .style(match self.input_mode {
-                InputMode::Normal => Style::default(),
-                InputMode::Editing => Style::default().fg(Color::Yellow),
-            })
-            .block(Block::bordered().title("Email"));
-        frame.render_widget(input, input_area);
+        self.input.draw(frame, input_area);
+        self.input_area = input_area;
         let help = Text::from(Line::from(match self.input_mode {
-            InputMode::Normal => "Press 'e' to start editing.",
-            InputMode::Editing => "Press 'Esc' to stop editing, 'Enter' to submit the email.",
+            InputMode::Normal => format!("Press '{}' to start editing.", self.edit_keys),
+            InputMode::Editing => {
+                "Press 'Esc' to stop editing, 'Enter' to submit the email.".to_string()
+            }
         }));
         frame.render_widget(Paragraph::new(help).wrap(Wrap { trim: false }), help_area);
+        let status = match &self.status {
+            Status::Idle => Line::default(),
+            Status::Processing => Line::from("Logging in...").italic(),
+            Status::Failed(err) => Line::from(format!("Login failed: {err}")).red(),
+            Status::Sent => Line::from("Code sent, check your inbox.").light_green(),
+        };
+        frame.render_widget(Paragraph::new(status), status_area);
 
-        match self.input_mode {
-            // Hide the cursor. `Frame` does this by default, so we don't need to do anything here
-            InputMode::Normal => {}
-
-            // Make the cursor visible and ask ratatui to put it at the specified coordinates after
-            // rendering
-            InputMode::Editing => frame.set_cursor_position(Position::new(
-                // Draw the cursor at the current position in the input field.
-                // This position can be controlled via the left and right arrow key
-                input_area.x + self.character_index as u16 + 1,
-                // Move one line down, from the border to the input line
-                input_area.y + 1,
-            )),
-        }
         frame.render_effect(&mut self.intro_effect, main_area, Duration::from_millis(40));
     }
 }
@@ -152,11 +172,21 @@ impl Component for Login {
 impl Login {
     pub fn new() -> Self {
         Self {
-            input: String::new(),
+            input: TextInput::new("Email")
+                .placeholder("you@example.com")
+                .max_length(MAX_EMAIL_LENGTH)
+                .validator(|email| {
+                    if is_valid_email(email.trim()) {
+                        Ok(())
+                    } else {
+                        Err("Invalid email".to_string())
+                    }
+                }),
             input_mode: InputMode::Normal,
-            messages: Vec::new(),
-            character_index: 0,
+            edit_keys: "Tab".to_string(),
             tx: None,
+            status: Status::Idle,
+            input_area: Rect::default(),
             intro_effect: fx::sequence(&[
                 // fx::ping_pong(fx::sweep_in(
                 //     Motion::LeftToRight,
@@ -178,73 +208,85 @@ impl Login {
             ]),
         }
     }
-    fn move_cursor_left(&mut self) {
-        let cursor_moved_left = self.character_index.saturating_sub(1);
-        self.character_index = self.clamp_cursor(cursor_moved_left);
-    }
-
-    fn move_cursor_right(&mut self) {
-        let cursor_moved_right = self.character_index.saturating_add(1);
-        self.character_index = self.clamp_cursor(cursor_moved_right);
-    }
-
-    fn enter_char(&mut self, new_char: char) {
-        let index = self.byte_index();
-        self.input.insert(index, new_char);
-        self.move_cursor_right();
+    /// Keys bound to start editing, shown in the hint. Tab always works, as it moves the
+    /// focus to the input.
+    pub fn edit_keys(mut self, keys: Option<&KeySequence>) -> Self {
+        if let Some(keys) = keys {
+            self.edit_keys = keys.to_string();
+        }
+        self
     }
 
-    /// Returns the byte index based on the character position.
-    ///
-    /// Since each character in a string can contain multiple bytes, it's necessary to calculate
-    /// the byte index based on the index of the character.
-    fn byte_index(&self) -> usize {
+    fn set_input_mode(&mut self, input_mode: InputMode) {
         self.input
-            .char_indices()
-            .map(|(i, _)| i)
-            .nth(self.character_index)
-            .unwrap_or(self.input.len())
+            .set_focused(matches!(input_mode, InputMode::Editing));
+        self.input_mode = input_mode;
     }
 
-    fn delete_char(&mut self) {
-        let is_not_cursor_leftmost = self.character_index != 0;
-        if is_not_cursor_leftmost {
-            // Method "remove" is not used on the saved text for deleting the selected char.
-            // Reason: Using remove on String works on bytes instead of the chars.
-            // Using remove would require special care because of char boundaries.
-
-            let current_index = self.character_index;
-            let from_left_to_current_index = current_index - 1;
-
-            // Getting all characters before the selected character.
-            let before_char_to_delete = self.input.chars().take(from_left_to_current_index);
-            // Getting all characters after selected character.
-            let after_char_to_delete = self.input.chars().skip(current_index);
-
-            // Put all characters together except the selected one.
-            // By leaving the selected one out, it is forgotten and therefore deleted.
-            self.input = before_char_to_delete.chain(after_char_to_delete).collect();
-            self.move_cursor_left();
+    fn submit_message(&mut self) {
+        if self.input.validate().is_err() {
+            return;
+        }
+        self.set_input_mode(InputMode::Normal);
+        // Keep the input around so a rejected address can be corrected
+        if let Some(tx) = self.tx.as_ref() {
+            tx.send(Action::SubmitEmail(self.input.value().to_string()))
+                .ok();
         }
     }
+}
 
-    fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
-        new_cursor_pos.clamp(0, self.input.chars().count())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::key;
+    use crate::utils::{buffer_lines, headless_terminal, FpsTrackingBackend};
+    use tokio::sync::mpsc::unbounded_channel;
+
+    fn render(login: &mut Login) -> Vec<String> {
+        let mut terminal = headless_terminal::<FpsTrackingBackend>(60, 30).unwrap();
+        terminal.draw(|frame| login.draw(frame)).unwrap();
+        buffer_lines(terminal.backend().buffer().unwrap())
     }
 
-    fn reset_cursor(&mut self) {
-        self.character_index = 0;
+    #[test]
+    fn draws_the_prompt_and_registers_the_input() {
+        let keys: KeySequence = "ctrl+e".parse().unwrap();
+        let mut login = Login::new().edit_keys(Some(&keys));
+        let lines = render(&mut login);
+        assert!(lines
+            .iter()
+            .any(|line| line.contains("Please enter your email to login.")));
+        assert!(lines
+            .iter()
+            .any(|line| line.contains("Press 'ctrl+e' to start editing.")));
+        let areas = login.focus_areas();
+        assert_eq!(areas.len(), 1);
+        assert_eq!(areas[0].id, EMAIL);
+        assert!(!areas[0].area.is_empty());
+        assert!(!areas[0].focused);
     }
 
-    fn submit_message(&mut self) {
-        self.messages.push(self.input.clone());
-        self.input.clear();
-        self.reset_cursor();
-        self.input_mode = InputMode::Normal;
-        self.tx
-            .as_ref()
-            .unwrap()
-            .send(Action::SubmitEmail(self.input.clone()))
-            .ok();
+    #[test]
+    fn focused_input_submits_the_email() {
+        let (tx, mut rx) = unbounded_channel();
+        let mut login = Login::new();
+        login.register_action_handler(tx).unwrap();
+        login.update(Action::Focus(Some(EMAIL))).unwrap();
+        for c in "me@example.com".chars() {
+            assert_eq!(login.handle_events(key(KeyCode::Char(c))), Some(true));
+        }
+        let lines = render(&mut login);
+        assert!(lines.iter().any(|line| line.contains("me@example.com")));
+        assert!(lines
+            .iter()
+            .any(|line| line.contains("Press 'Esc' to stop editing")));
+
+        login.handle_events(key(KeyCode::Enter));
+        assert_eq!(
+            rx.try_recv(),
+            Ok(Action::SubmitEmail("me@example.com".to_string()))
+        );
+        assert!(login.focus_areas().iter().all(|area| !area.focused));
     }
 }
@@ -1,40 +1,68 @@
 pub mod components;
 mod login;
 pub mod notfound;
-use crate::utils::{Action, Result};
+mod verify;
+use crate::utils::{Action, FocusArea, Result};
 pub use login::*;
 use ratzilla::{
     event::{KeyEvent, MouseEvent},
-    ratatui::Frame,
+    ratatui::{layout::Rect, Frame},
 };
 use tokio::sync::mpsc::UnboundedSender;
+pub use verify::*;
 
+/// A page or overlay managed by `App`.
+///
+/// Lifecycle hooks are called by `App` in this order: `on_mount` once after the action
+/// handler is registered, then `on_enter`/`on_leave` each time the page is shown or hidden,
+/// with `on_resize` whenever the terminal size changes.
 pub trait Component {
-    // #[allow(unused_variables)]
-    // fn register_layout_handler(&mut self, area: Rect) -> Result<()> {
-    //     Ok(())
-    // }
-    // fn unfocus(&mut self) -> Result<()> {
-    //     Ok(())
-    // }
-    //
-    // fn focus(&mut self) -> Result<()> {
-    //     Ok(())
-    // }
-    //
-    // fn is_focused(&self) -> bool {
-    //     true
-    // }
     #[allow(unused_variables)]
     fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
         Ok(())
     }
+    /// Called once, after the action handler is registered and before the first draw.
+    fn on_mount(&mut self) -> Result<()> {
+        Ok(())
+    }
+    /// Called when the page becomes the current page.
+    fn on_enter(&mut self) -> Result<()> {
+        Ok(())
+    }
+    /// Called when another page replaces this one.
+    fn on_leave(&mut self) -> Result<()> {
+        Ok(())
+    }
+    /// Called with the full terminal area when it changes size, and once before the first
+    /// draw.
     #[allow(unused_variables)]
-    fn handle_mouse(&mut self, mouse: MouseEvent) -> Result<Option<Action>> {
-        Ok(None)
+    fn on_resize(&mut self, area: Rect) -> Result<()> {
+        Ok(())
     }
+    /// Whether the component takes all keyboard and mouse input, like an open modal.
+    fn captures_input(&self) -> bool {
+        false
+    }
+    /// Widgets that can take focus, in Tab order, with the areas they were last drawn in.
+    ///
+    /// Read after every draw. `App` moves focus between them with Tab and mouse clicks by
+    /// sending `Action::Focus`.
+    fn focus_areas(&self) -> Vec<FocusArea> {
+        Vec::new()
+    }
+    /// Text to copy on Ctrl+C, e.g. the value of the focused input.
+    fn copy_text(&mut self) -> Option<String> {
+        None
+    }
+    /// Handle a mouse event, returning `Some(true)` if it was used, e.g. a click on an
+    /// overlay, so it doesn't reach what is drawn under it.
     #[allow(unused_variables)]
-    fn handle_actions(&mut self, action: Option<Action>) -> Result<Option<Action>> {
+    fn handle_mouse(&mut self, mouse: MouseEvent) -> Option<bool> {
+        None
+    }
+    /// React to an action, optionally answering with a follow-up action.
+    #[allow(unused_variables)]
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
         Ok(None)
     }
 
@@ -42,13 +70,6 @@ pub trait Component {
     fn handle_events(&mut self, key: KeyEvent) -> Option<bool> {
         None
     }
-    // #[allow(unused_variables)]
-    // fn handle_mouse_events(mouse: MouseEvent) -> Result<Option<Action>> {
-    //     Ok(None)
-    // }
-    // #[allow(unused_variables)]
-    // fn update(&mut self, action: Action, ctx: &Ctx) -> Result<Option<Action>> {
-    //     Ok(None)
-    // }
+    #[allow(unused_variables)]
     fn draw(&mut self, f: &mut Frame<'_>) {}
 }
@@ -1,19 +1,8 @@
-use std::collections::HashMap;
-
-use derive_deref::{Deref, DerefMut};
 use ratatui::widgets::Wrap;
-use ratzilla::event::KeyEvent;
-use ratzilla::ratatui::layout::{Constraint, Layout, Position};
-use ratzilla::ratatui::style::{Modifier, Style, Stylize};
-use ratzilla::ratatui::text::{Line, Span, Text};
-use ratzilla::ratatui::widgets::{List, ListItem};
+use ratzilla::ratatui::layout::{Constraint, Layout};
+use ratzilla::ratatui::text::{Line, Text};
+use ratzilla::ratatui::widgets::Paragraph;
 use ratzilla::ratatui::Frame;
-use ratzilla::ratatui::{
-    style::Color,
-    widgets::{Block, Paragraph},
-};
-use ratzilla::{event::KeyCode, WebRenderer};
-use tachyonfx::{Effect, EffectRenderer};
 
 use crate::pages::Component;
 
@@ -52,3 +41,18 @@ impl NotFound {
         Self {}
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{buffer_lines, headless_terminal, FpsTrackingBackend};
+
+    #[test]
+    fn draws_the_not_found_notice() {
+        let mut terminal = headless_terminal::<FpsTrackingBackend>(60, 10).unwrap();
+        terminal.draw(|frame| NotFound::new().draw(frame)).unwrap();
+        let lines = buffer_lines(terminal.backend().buffer().unwrap());
+        assert!(lines.iter().any(|line| line.trim() == "[404]"));
+        assert!(lines.iter().any(|line| line.contains("Page not found!")));
+    }
+}
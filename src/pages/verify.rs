@@ -1,10 +1,9 @@
 use crate::app::Page;
+use crate::utils::auth::CODE_LENGTH;
 use crate::utils::{Action, Result};
-use crate::APP_NAME;
 use ratatui::widgets::Wrap;
-use ratzilla::event::{KeyCode, MouseButton, MouseEvent};
-use ratzilla::event::{KeyEvent, MouseEventKind};
-use ratzilla::ratatui::layout::{Constraint, Layout, Position};
+use ratzilla::event::{KeyCode, KeyEvent};
+use ratzilla::ratatui::layout::{Constraint, Flex, Layout, Position};
 use ratzilla::ratatui::prelude::*;
 use ratzilla::ratatui::style::{Style, Stylize};
 use ratzilla::ratatui::text::{Line, Text};
@@ -14,237 +13,340 @@ use ratzilla::ratatui::{
     widgets::Clear,
     widgets::{Block, Paragraph},
 };
-use tachyonfx::fx::RepeatMode;
-use tachyonfx::{
-    fx, CenteredShrink, Duration, Effect, EffectRenderer, EffectTimer, Interpolation, Motion,
-};
+use tachyonfx::CenteredShrink;
 use tokio::sync::mpsc::UnboundedSender;
+use web_time::{Duration, Instant};
 
 use crate::pages::Component;
 
-pub enum InputMode {
-    Normal,
-    Editing,
+/// Time the user has to wait before another code can be requested
+const RESEND_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Outcome of the last submitted code
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Status {
+    Idle,
+    Processing,
+    Failed(String),
 }
 
-/// App holds the state of the application
-pub struct Login {
-    /// Current value of the input box
-    input: String,
-    /// Position of cursor in the editor area.
-    character_index: usize,
-    /// Current input mode
-    input_mode: InputMode,
-    /// History of recorded messages
-    messages: Vec<String>,
+/// One-time code entry shown after an email has been submitted
+pub struct Verify {
+    /// Digits entered so far
+    digits: Vec<char>,
+    /// Box the next digit goes into
+    cursor: usize,
+    /// Address the code was sent to
+    email: Option<String>,
+    /// When the last code was sent, used for the resend cooldown
+    sent_at: Option<Instant>,
     // Action Handler
     tx: Option<UnboundedSender<Action>>,
-    // Effect
-    intro_effect: Effect,
+    // Progress of the verify request
+    status: Status,
 }
 
-impl Component for Login {
+impl Component for Verify {
     fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
         self.tx = Some(tx);
         Ok(())
     }
-    fn handle_mouse(&mut self, mouse: MouseEvent) -> Result<Option<Action>> {
-        match mouse.button {
-            MouseButton::Left => match mouse.event {
-                MouseEventKind::Pressed => {
-                    // Set focus to input box on click
-                    self.input_mode = InputMode::Editing;
-                }
 
-                _ => {}
-            },
+    fn on_leave(&mut self) -> Result<()> {
+        // The request is cancelled along with the page
+        if self.status == Status::Processing {
+            self.status = Status::Idle;
+        }
+        Ok(())
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::CodeSent(email) => {
+                self.email = Some(email);
+                self.sent_at = Some(Instant::now());
+                self.status = Status::Idle;
+                self.clear();
+            }
+            Action::EnterProcessing => self.status = Status::Processing,
+            Action::Error(err) => {
+                self.status = Status::Failed(err);
+                self.clear();
+            }
+            Action::Paste(text) => self.paste(&text),
+            Action::LoggedIn => {
+                self.status = Status::Idle;
+                self.clear();
+            }
             _ => {}
-        };
+        }
         Ok(None)
     }
+
     fn handle_events(&mut self, key_event: KeyEvent) -> Option<bool> {
-        match self.input_mode {
-            InputMode::Normal => {
-                if let KeyCode::Char('i') = key_event.code {
-                    self.tx
-                        .as_ref()
-                        .unwrap()
-                        .send(Action::ChangePage(Page::Home))
-                        .ok();
-                }
-                if let KeyCode::Char('e') = key_event.code {
-                    self.input_mode = InputMode::Editing;
-                }
-                None
-            }
-            InputMode::Editing => {
-                match key_event.code {
-                    KeyCode::Enter => self.submit_message(),
-                    KeyCode::Char(to_insert) => self.enter_char(to_insert),
-                    KeyCode::Backspace => self.delete_char(),
-                    KeyCode::Left => self.move_cursor_left(),
-                    KeyCode::Right => self.move_cursor_right(),
-                    KeyCode::Esc => self.input_mode = InputMode::Normal,
-                    _ => {}
-                }
-                Some(true)
+        // Esc still goes back, which cancels the request along with the page
+        if self.status == Status::Processing && key_event.code != KeyCode::Esc {
+            return Some(true);
+        }
+        match key_event.code {
+            KeyCode::Char(digit) if digit.is_ascii_digit() => self.enter_digit(digit),
+            KeyCode::Char('r') => self.resend(),
+            KeyCode::Backspace => self.delete_digit(),
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => {
+                self.cursor = (self.cursor + 1)
+                    .min(self.digits.len())
+                    .min(CODE_LENGTH - 1)
             }
+            KeyCode::Enter => self.submit(),
+            KeyCode::Esc => self.send(Action::ChangePage(Page::Login)),
+            _ => return None,
         }
+        Some(true)
     }
 
     fn draw(&mut self, frame: &mut Frame) {
         Clear.render(frame.area(), frame.buffer_mut());
-        let area = frame.area().inner_centered(40, 25);
-        let input = Layout::vertical([
-            Constraint::Min(1),
+        let area = frame.area().inner_centered(50, 12);
+        let layout = Layout::vertical([
             Constraint::Length(2),
-            Constraint::Length(1),
             Constraint::Length(3),
+            Constraint::Length(1),
             Constraint::Length(2),
-            Constraint::Min(1),
-        ]);
-        let [_, main_area, text_area, input_area, help_area, _] = input.areas(area);
-        let string = APP_NAME
-            .to_uppercase()
-            .chars()
-            .map(|c| format!(" {} ", c))
-            .collect::<String>();
-
-        let main_text = Text::from(vec![
-            Line::from(format!("-------- {} --------", string)).bold()
+            Constraint::Length(1),
         ]);
-        //render_menu(f, state);
-        frame.render_widget(main_text.light_green().centered(), main_area);
+        let [text_area, code_area, status_area, help_area, resend_area] = layout.areas(area);
 
-        let text = Text::from(Line::from("Please enter your email to login."));
-        frame.render_widget(Paragraph::new(text).wrap(Wrap { trim: false }), text_area);
-        let input = Paragraph::new(self.input.as_str())
-            .style(match self.input_mode {
-                InputMode::Normal => Style::default(),
-                InputMode::Editing => Style::default().fg(Color::Yellow),
-            })
-            .block(Block::bordered().title("Email"));
-        frame.render_widget(input, input_area);
-        let help = Text::from(Line::from(match self.input_mode {
-            InputMode::Normal => "Press 'e' to start editing.",
-            InputMode::Editing => "Press 'Esc' to stop editing, 'Enter' to submit the email.",
+        let text = Text::from(Line::from(match &self.email {
+            Some(email) => format!("Enter the {CODE_LENGTH}-digit code sent to {email}."),
+            None => format!("Enter the {CODE_LENGTH}-digit code from your email."),
         }));
+        frame.render_widget(Paragraph::new(text).wrap(Wrap { trim: false }), text_area);
+
+        let boxes = Layout::horizontal([Constraint::Length(5); CODE_LENGTH])
+            .spacing(1)
+            .flex(Flex::Center)
+            .split(code_area);
+        for (index, area) in boxes.iter().enumerate() {
+            let digit = self.digits.get(index).map(char::to_string);
+            let style = if index == self.cursor {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
+            };
+            frame.render_widget(
+                Paragraph::new(digit.unwrap_or_default())
+                    .centered()
+                    .block(Block::bordered().border_style(style)),
+                *area,
+            );
+        }
+
+        let status = match &self.status {
+            Status::Idle => Line::default(),
+            Status::Processing => Line::from("Verifying...").italic(),
+            Status::Failed(err) => Line::from(format!("Verification failed: {err}")).red(),
+        };
+        frame.render_widget(Paragraph::new(status), status_area);
+
+        let help = Text::from(Line::from(
+            "Type or paste the code, 'Enter' to submit, 'Esc' to change the email.",
+        ));
         frame.render_widget(Paragraph::new(help).wrap(Wrap { trim: false }), help_area);
 
-        match self.input_mode {
-            // Hide the cursor. `Frame` does this by default, so we don't need to do anything here
-            InputMode::Normal => {}
+        let resend = match self.cooldown_remaining() {
+            Some(remaining) => Line::from(format!(
+                "You can request a new code in {}s.",
+                remaining.as_secs() + 1
+            ))
+            .dark_gray(),
+            None => Line::from("Press 'r' to resend the code."),
+        };
+        frame.render_widget(Paragraph::new(resend), resend_area);
 
-            // Make the cursor visible and ask ratatui to put it at the specified coordinates after
-            // rendering
-            InputMode::Editing => frame.set_cursor_position(Position::new(
-                // Draw the cursor at the current position in the input field.
-                // This position can be controlled via the left and right arrow key
-                input_area.x + self.character_index as u16 + 1,
-                // Move one line down, from the border to the input line
-                input_area.y + 1,
-            )),
+        if self.status != Status::Processing {
+            if let Some(area) = boxes.get(self.cursor) {
+                frame.set_cursor_position(Position::new(area.x + 2, area.y + 1));
+            }
         }
-        frame.render_effect(&mut self.intro_effect, main_area, Duration::from_millis(40));
     }
 }
 
-impl Login {
+impl Verify {
     pub fn new() -> Self {
         Self {
-            input: String::new(),
-            input_mode: InputMode::Normal,
-            messages: Vec::new(),
-            character_index: 0,
+            digits: Vec::with_capacity(CODE_LENGTH),
+            cursor: 0,
+            email: None,
+            sent_at: None,
             tx: None,
-            intro_effect: fx::sequence(&[
-                // fx::ping_pong(fx::sweep_in(
-                //     Motion::LeftToRight,
-                //     10,
-                //     0,
-                //     Color::Black,
-                //     EffectTimer::from_ms(3000, Interpolation::QuadIn),
-                // )),
-                fx::coalesce((3000, Interpolation::SineOut)),
-                fx::sleep(1000),
-                fx::repeat(
-                    fx::hsl_shift(
-                        Some([120.0, 25.0, 25.0]),
-                        None,
-                        (5000, Interpolation::Linear),
-                    ),
-                    RepeatMode::Forever,
-                ),
-            ]),
-        }
-    }
-    fn move_cursor_left(&mut self) {
-        let cursor_moved_left = self.character_index.saturating_sub(1);
-        self.character_index = self.clamp_cursor(cursor_moved_left);
-    }
-
-    fn move_cursor_right(&mut self) {
-        let cursor_moved_right = self.character_index.saturating_add(1);
-        self.character_index = self.clamp_cursor(cursor_moved_right);
-    }
-
-    fn enter_char(&mut self, new_char: char) {
-        let index = self.byte_index();
-        self.input.insert(index, new_char);
-        self.move_cursor_right();
-    }
-
-    /// Returns the byte index based on the character position.
-    ///
-    /// Since each character in a string can contain multiple bytes, it's necessary to calculate
-    /// the byte index based on the index of the character.
-    fn byte_index(&self) -> usize {
-        self.input
-            .char_indices()
-            .map(|(i, _)| i)
-            .nth(self.character_index)
-            .unwrap_or(self.input.len())
-    }
-
-    fn delete_char(&mut self) {
-        let is_not_cursor_leftmost = self.character_index != 0;
-        if is_not_cursor_leftmost {
-            // Method "remove" is not used on the saved text for deleting the selected char.
-            // Reason: Using remove on String works on bytes instead of the chars.
-            // Using remove would require special care because of char boundaries.
-
-            let current_index = self.character_index;
-            let from_left_to_current_index = current_index - 1;
-
-            // Getting all characters before the selected character.
-            let before_char_to_delete = self.input.chars().take(from_left_to_current_index);
-            // Getting all characters after selected character.
-            let after_char_to_delete = self.input.chars().skip(current_index);
-
-            // Put all characters together except the selected one.
-            // By leaving the selected one out, it is forgotten and therefore deleted.
-            self.input = before_char_to_delete.chain(after_char_to_delete).collect();
-            self.move_cursor_left();
-        }
-    }
-
-    fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
-        new_cursor_pos.clamp(0, self.input.chars().count())
-    }
-
-    fn reset_cursor(&mut self) {
-        self.character_index = 0;
-    }
-
-    fn submit_message(&mut self) {
-        self.messages.push(self.input.clone());
-        self.input.clear();
-        self.reset_cursor();
-        self.input_mode = InputMode::Normal;
-        self.tx
-            .as_ref()
-            .unwrap()
-            .send(Action::SubmitEmail(self.input.clone()))
-            .ok();
+            status: Status::Idle,
+        }
+    }
+
+    fn send(&self, action: Action) {
+        if let Some(tx) = self.tx.as_ref() {
+            tx.send(action).ok();
+        }
+    }
+
+    fn enter_digit(&mut self, digit: char) {
+        if self.cursor < self.digits.len() {
+            self.digits[self.cursor] = digit;
+        } else if self.digits.len() < CODE_LENGTH {
+            self.digits.push(digit);
+        }
+        self.cursor = (self.cursor + 1)
+            .min(CODE_LENGTH - 1)
+            .min(self.digits.len());
+        if self.digits.len() == CODE_LENGTH && self.cursor == CODE_LENGTH - 1 {
+            self.submit();
+        }
+    }
+
+    fn delete_digit(&mut self) {
+        if self.cursor < self.digits.len() && self.cursor == CODE_LENGTH - 1 {
+            // The last box keeps the cursor, so clear it in place first
+            self.digits.remove(self.cursor);
+        } else if self.cursor > 0 {
+            self.cursor -= 1;
+            self.digits.remove(self.cursor);
+        }
+    }
+
+    /// Fill the boxes from pasted text, ignoring anything that isn't a digit.
+    fn paste(&mut self, text: &str) {
+        let digits: Vec<char> = text.chars().filter(char::is_ascii_digit).collect();
+        if digits.is_empty() {
+            return;
+        }
+        self.digits = digits.into_iter().take(CODE_LENGTH).collect();
+        self.cursor = self.digits.len().min(CODE_LENGTH - 1);
+        self.submit();
+    }
+
+    fn clear(&mut self) {
+        self.digits.clear();
+        self.cursor = 0;
+    }
+
+    fn submit(&mut self) {
+        if self.digits.len() == CODE_LENGTH {
+            self.send(Action::SubmitCode(self.digits.iter().collect()));
+        }
+    }
+
+    fn cooldown_remaining(&self) -> Option<Duration> {
+        self.sent_at
+            .and_then(|sent_at| RESEND_COOLDOWN.checked_sub(sent_at.elapsed()))
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    fn resend(&mut self) {
+        if self.cooldown_remaining().is_none() {
+            self.status = Status::Idle;
+            self.send(Action::ResendCode);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::key;
+    use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+    fn verify() -> (Verify, UnboundedReceiver<Action>) {
+        let (tx, rx) = unbounded_channel();
+        let mut verify = Verify::new();
+        verify.register_action_handler(tx).unwrap();
+        (verify, rx)
+    }
+
+    fn type_keys(verify: &mut Verify, text: &str) {
+        for c in text.chars() {
+            verify.handle_events(key(KeyCode::Char(c)));
+        }
+    }
+
+    #[test]
+    fn digits_fill_the_boxes_and_overwrite() {
+        let (mut verify, mut rx) = verify();
+        type_keys(&mut verify, "12a3");
+        assert_eq!(verify.digits, vec!['1', '2', '3']);
+        assert_eq!(verify.cursor, 3);
+        verify.handle_events(key(KeyCode::Left));
+        verify.handle_events(key(KeyCode::Left));
+        type_keys(&mut verify, "9");
+        assert_eq!(verify.digits, vec!['1', '9', '3']);
+        assert_eq!(verify.cursor, 2);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn cursor_stays_on_the_entered_boxes() {
+        let (mut verify, _rx) = verify();
+        type_keys(&mut verify, "12");
+        verify.handle_events(key(KeyCode::Right));
+        assert_eq!(verify.cursor, 2);
+        for _ in 0..3 {
+            verify.handle_events(key(KeyCode::Left));
+        }
+        assert_eq!(verify.cursor, 0);
+        verify.handle_events(key(KeyCode::Right));
+        assert_eq!(verify.cursor, 1);
+    }
+
+    #[test]
+    fn the_last_digit_submits_and_backspace_clears_it_in_place() {
+        let (mut verify, mut rx) = verify();
+        type_keys(&mut verify, "123456");
+        assert_eq!(rx.try_recv(), Ok(Action::SubmitCode("123456".to_string())));
+        assert_eq!(verify.cursor, CODE_LENGTH - 1);
+        verify.handle_events(key(KeyCode::Backspace));
+        assert_eq!(verify.digits, vec!['1', '2', '3', '4', '5']);
+        assert_eq!(verify.cursor, CODE_LENGTH - 1);
+        verify.handle_events(key(KeyCode::Backspace));
+        assert_eq!(verify.digits, vec!['1', '2', '3', '4']);
+        assert_eq!(verify.cursor, 4);
+    }
+
+    #[test]
+    fn pastes_keep_the_digits_and_submit_full_codes() {
+        let (mut verify, mut rx) = verify();
+        verify
+            .update(Action::Paste("code: none".to_string()))
+            .unwrap();
+        assert!(verify.digits.is_empty());
+        verify.update(Action::Paste("1 2".to_string())).unwrap();
+        assert_eq!(verify.digits, vec!['1', '2']);
+        assert!(rx.try_recv().is_err());
+        verify
+            .update(Action::Paste("123-456-789".to_string()))
+            .unwrap();
+        assert_eq!(rx.try_recv(), Ok(Action::SubmitCode("123456".to_string())));
+    }
+
+    #[test]
+    fn codes_can_only_be_resent_after_the_cooldown() {
+        let (mut verify, mut rx) = verify();
+        verify
+            .update(Action::CodeSent("me@example.com".to_string()))
+            .unwrap();
+        type_keys(&mut verify, "r");
+        assert!(rx.try_recv().is_err());
+        verify.sent_at = Instant::now().checked_sub(RESEND_COOLDOWN);
+        type_keys(&mut verify, "r");
+        assert_eq!(rx.try_recv(), Ok(Action::ResendCode));
+    }
+
+    #[test]
+    fn only_esc_gets_through_while_processing() {
+        let (mut verify, mut rx) = verify();
+        verify.update(Action::EnterProcessing).unwrap();
+        assert_eq!(verify.handle_events(key(KeyCode::Char('1'))), Some(true));
+        assert!(verify.digits.is_empty());
+        assert_eq!(verify.handle_events(key(KeyCode::Esc)), Some(true));
+        assert_eq!(rx.try_recv(), Ok(Action::ChangePage(Page::Login)));
     }
 }
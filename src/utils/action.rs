@@ -1,12 +1,23 @@
 use ratzilla::event::MouseEvent;
 
 use crate::app::Page;
+use crate::pages::components::{Modal, PaletteEntry, Severity};
+use crate::utils::session::Session;
+use crate::utils::{BackendType, HelpSection, KeySequence, Theme};
 
+// disable unused warnings for now
+#[allow(dead_code)]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Action {
     Tick,
     Render,
     SubmitEmail(String),
+    CodeSent(String),
+    SubmitCode(String),
+    ResendCode,
+    StartSession(Session),
+    Paste(String),
+    Copy(String),
     Resize(u16, u16),
     Mouse(MouseEvent),
     ToggleNav,
@@ -17,6 +28,9 @@ pub enum Action {
     Error(String),
     Back,
     Forward,
+    GoBack,
+    GoForward,
+    ClearHistory,
 
     ChangePage(Page),
     NextView,
@@ -32,6 +46,17 @@ pub enum Action {
     CloseToast,
 
     ToggleShowHelp,
+    /// Open the help with these bindings and the keys that close it again, or close it
+    ShowHelp(Vec<HelpSection>, Option<KeySequence>),
+    TogglePalette,
+    ShowPalette(Vec<PaletteEntry>),
+    SetBackend(BackendType),
+    SetTheme(Theme),
+    OpenCommandLine,
+    /// Focus a widget of the current page by its id, or none
+    Focus(Option<&'static str>),
+    /// Start recording the session, or stop and save the recording
+    ToggleRecording,
     ToggleShowQuit,
     ToggleUsers,
     ToggleChats,
@@ -47,9 +72,13 @@ pub enum Action {
     Decrement(usize),
     CompleteInput(String),
     Login,
+    Logout,
     Register,
     Toast(String, String),
+    Notify(Severity, String, String),
     Popup(String, String),
+    ShowModal(Modal),
+    SubmitForm(String, Vec<String>),
     EnterNormal,
     EnterInput,
     LoggedIn,
@@ -60,3 +89,11 @@ pub enum Action {
     Update,
     FocusInput,
 }
+
+impl Action {
+    /// Whether the action carries input from the user, which only goes to the component
+    /// capturing input when there is one, like keys do.
+    pub fn is_input(&self) -> bool {
+        matches!(self, Action::Paste(_))
+    }
+}
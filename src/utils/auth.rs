@@ -0,0 +1,419 @@
+//! Client for the authentication API.
+//!
+//! The functions in this module are plain futures that only talk to the network and report
+//! back through the action channel, so they can be spawned from the render loop in the
+//! browser and driven by any executor in tests.
+
+use reqwest::StatusCode;
+use serde::Serialize;
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::session::Session;
+use super::{browser_window, Action, Error, Result};
+
+/// Base URL used when `TTYX_API_URL` is not set at compile time.
+pub const DEFAULT_API_URL: &str = "http://localhost:8080";
+
+/// Meta tag through which the hosting page can point the app at another API.
+pub const API_URL_META: &str = r#"meta[name="ttyx-api-url"]"#;
+
+/// Path of the endpoint that starts an email login.
+pub const REGISTER_PATH: &str = "/api/auth/register";
+
+/// Path of the endpoint that exchanges a one-time code for a session token.
+pub const VERIFY_PATH: &str = "/api/auth/verify";
+
+/// Number of digits in the one-time code sent by email.
+pub const CODE_LENGTH: usize = 6;
+
+/// Location of the authentication API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthConfig {
+    /// Scheme, host and port of the API, without a trailing path.
+    pub base_url: String,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self::new(option_env!("TTYX_API_URL").unwrap_or(DEFAULT_API_URL))
+    }
+}
+
+impl AuthConfig {
+    /// Create a configuration pointing at the given base URL.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+        }
+    }
+
+    /// Configuration chosen at runtime, from the page's `<meta name="ttyx-api-url">` in a
+    /// browser or the `TTYX_API_URL` environment variable natively.
+    ///
+    /// Returns `None` when neither is set, leaving the compiled-in default.
+    pub fn from_environment() -> Option<Self> {
+        let base_url = match browser_window() {
+            Some(window) => window
+                .document()?
+                .query_selector(API_URL_META)
+                .ok()??
+                .get_attribute("content"),
+            None => std::env::var("TTYX_API_URL").ok(),
+        }?;
+        let base_url = base_url.trim();
+        (!base_url.is_empty()).then(|| Self::new(base_url))
+    }
+
+    /// Build the full URL for an API path.
+    pub fn endpoint(&self, path: &str) -> String {
+        format!("{}{}", self.base_url.trim_end_matches('/'), path)
+    }
+}
+
+#[derive(Serialize)]
+struct RegisterRequest<'a> {
+    email: &'a str,
+}
+
+#[derive(Serialize)]
+struct VerifyRequest<'a> {
+    email: &'a str,
+    code: &'a str,
+}
+
+/// Post the email to the register endpoint.
+///
+/// # Errors
+///
+/// - `InvalidEmail` if the address is malformed or rejected by the server
+/// - `Unauthorized` if the server refuses the login
+/// - `FailedRequest` if the server can't be reached or answers with anything else
+pub async fn register(config: &AuthConfig, email: &str) -> Result<()> {
+    let email = email.trim();
+    if !is_valid_email(email) {
+        return Err(Error::InvalidEmail);
+    }
+    let response = reqwest::Client::new()
+        .post(config.endpoint(REGISTER_PATH))
+        .json(&RegisterRequest { email })
+        .send()
+        .await
+        .map_err(|err| {
+            log::error!("Failed to login: {}", err);
+            Error::FailedRequest
+        })?;
+    status_to_result(response.status())
+}
+
+/// Exchange the one-time code sent to `email` for a session.
+///
+/// # Errors
+///
+/// - `BadRequest` if the code is malformed or rejected by the server
+/// - `Unauthorized` if the code is wrong or expired
+/// - `FailedRequest` if the server can't be reached or the response can't be read
+pub async fn verify(config: &AuthConfig, email: &str, code: &str) -> Result<Session> {
+    if code.len() != CODE_LENGTH || !code.chars().all(|c| c.is_ascii_digit()) {
+        return Err(Error::BadRequest(format!(
+            "Code must be {CODE_LENGTH} digits"
+        )));
+    }
+    let response = reqwest::Client::new()
+        .post(config.endpoint(VERIFY_PATH))
+        .json(&VerifyRequest {
+            email: email.trim(),
+            code,
+        })
+        .send()
+        .await
+        .map_err(|err| {
+            log::error!("Failed to verify code: {}", err);
+            Error::FailedRequest
+        })?;
+    match response.status() {
+        StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => {
+            return Err(Error::BadRequest("Invalid code".to_string()))
+        }
+        status => status_to_result(status)?,
+    }
+    response.json::<Session>().await.map_err(|err| {
+        log::error!("Failed to read session: {}", err);
+        Error::FailedRequest
+    })
+}
+
+/// Run the whole login flow for `email`, reporting progress through `tx`.
+///
+/// Sends `Action::EnterProcessing` before the request, then either `Action::CodeSent` or
+/// `Action::Error` carrying the mapped error.
+pub async fn submit_email(config: AuthConfig, email: String, tx: UnboundedSender<Action>) {
+    tx.send(Action::EnterProcessing).ok();
+    let action = match register(&config, &email).await {
+        Ok(()) => {
+            log::info!("Login code sent to {}", email);
+            Action::CodeSent(email)
+        }
+        Err(err) => Action::Error(err.to_string()),
+    };
+    tx.send(action).ok();
+}
+
+/// Verify the one-time code for `email`, reporting progress through `tx`.
+///
+/// Sends `Action::EnterProcessing` before the request, then either `Action::StartSession` or
+/// `Action::Error` carrying the mapped error.
+pub async fn submit_code(
+    config: AuthConfig,
+    email: String,
+    code: String,
+    tx: UnboundedSender<Action>,
+) {
+    tx.send(Action::EnterProcessing).ok();
+    let action = match verify(&config, &email, &code).await {
+        Ok(session) => Action::StartSession(session),
+        Err(err) => Action::Error(err.to_string()),
+    };
+    tx.send(action).ok();
+}
+
+/// Map an HTTP status from the auth API to the application error type.
+fn status_to_result(status: StatusCode) -> Result<()> {
+    match status {
+        status if status.is_success() => Ok(()),
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(Error::Unauthorized),
+        StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => Err(Error::InvalidEmail),
+        status => {
+            log::error!("Unexpected response from auth API: {}", status);
+            Err(Error::FailedRequest)
+        }
+    }
+}
+
+/// Cheap sanity check so obviously broken addresses never leave the browser.
+pub fn is_valid_email(email: &str) -> bool {
+    match email.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && domain.contains('.')
+                && !email.contains(char::is_whitespace)
+                && !domain.contains('@')
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+    use tokio::sync::mpsc;
+
+    /// Minimal stand-in for the auth API: answers a single request with `status` and
+    /// returns the request line and body it received.
+    fn serve_once(status: u16) -> (AuthConfig, JoinHandle<(String, String)>) {
+        serve_once_with_body(status, "")
+    }
+
+    fn serve_once_with_body(
+        status: u16,
+        response_body: &'static str,
+    ) -> (AuthConfig, JoinHandle<(String, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = AuthConfig::new(format!("http://{}", listener.local_addr().unwrap()));
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            let response = format!(
+                "HTTP/1.1 {status} Stand-in\r\ncontent-type: application/json\r\n\
+                 content-length: {}\r\nconnection: close\r\n\r\n{response_body}",
+                response_body.len()
+            );
+            reader.get_mut().write_all(response.as_bytes()).unwrap();
+            (
+                request_line.trim().to_string(),
+                String::from_utf8(body).unwrap(),
+            )
+        });
+        (config, handle)
+    }
+
+    fn run_flow(config: AuthConfig, email: &str) -> Vec<Action> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        block_on(submit_email(config, email.to_string(), tx));
+        drain(rx)
+    }
+
+    fn run_verify(config: AuthConfig, code: &str) -> Vec<Action> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        block_on(submit_code(
+            config,
+            "user@example.com".to_string(),
+            code.to_string(),
+            tx,
+        ));
+        drain(rx)
+    }
+
+    fn block_on(future: impl std::future::Future<Output = ()>) {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future);
+    }
+
+    fn drain(mut rx: mpsc::UnboundedReceiver<Action>) -> Vec<Action> {
+        let mut actions = Vec::new();
+        while let Ok(action) = rx.try_recv() {
+            actions.push(action);
+        }
+        actions
+    }
+
+    #[test]
+    fn successful_login_posts_email() {
+        let (config, server) = serve_once(200);
+        let actions = run_flow(config, "user@example.com");
+        let (request_line, body) = server.join().unwrap();
+        assert_eq!(request_line, format!("POST {REGISTER_PATH} HTTP/1.1"));
+        assert_eq!(body, r#"{"email":"user@example.com"}"#);
+        assert_eq!(
+            actions,
+            vec![
+                Action::EnterProcessing,
+                Action::CodeSent("user@example.com".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn valid_code_starts_session() {
+        let (config, server) =
+            serve_once_with_body(200, r#"{"token":"abc","expires_at":1700000000}"#);
+        let actions = run_verify(config, "123456");
+        let (request_line, body) = server.join().unwrap();
+        assert_eq!(request_line, format!("POST {VERIFY_PATH} HTTP/1.1"));
+        assert_eq!(body, r#"{"email":"user@example.com","code":"123456"}"#);
+        assert_eq!(
+            actions,
+            vec![
+                Action::EnterProcessing,
+                Action::StartSession(Session {
+                    token: "abc".to_string(),
+                    expires_at: Some(1700000000),
+                })
+            ]
+        );
+    }
+
+    #[test]
+    fn rejected_code_is_an_error() {
+        let (config, server) = serve_once(401);
+        let actions = run_verify(config, "123456");
+        server.join().unwrap();
+        assert_eq!(
+            actions,
+            vec![
+                Action::EnterProcessing,
+                Action::Error(Error::Unauthorized.to_string())
+            ]
+        );
+
+        let (config, server) = serve_once_with_body(200, "not json");
+        let actions = run_verify(config, "123456");
+        server.join().unwrap();
+        assert_eq!(
+            actions,
+            vec![
+                Action::EnterProcessing,
+                Action::Error(Error::FailedRequest.to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn status_codes_map_to_errors() {
+        for (status, error) in [
+            (401, Error::Unauthorized),
+            (403, Error::Unauthorized),
+            (422, Error::InvalidEmail),
+            (500, Error::FailedRequest),
+        ] {
+            let (config, server) = serve_once(status);
+            let actions = run_flow(config, "user@example.com");
+            server.join().unwrap();
+            assert_eq!(
+                actions,
+                vec![Action::EnterProcessing, Action::Error(error.to_string())]
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_email_is_not_sent() {
+        let config = AuthConfig::new("http://127.0.0.1:9");
+        for email in [
+            "",
+            "user",
+            "user@",
+            "@example.com",
+            "user@example",
+            "a b@example.com",
+        ] {
+            assert_eq!(
+                run_flow(config.clone(), email),
+                vec![
+                    Action::EnterProcessing,
+                    Action::Error(Error::InvalidEmail.to_string())
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn unreachable_server_is_a_failed_request() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = AuthConfig::new(format!("http://{}", listener.local_addr().unwrap()));
+        drop(listener);
+        assert_eq!(
+            run_flow(config, "user@example.com"),
+            vec![
+                Action::EnterProcessing,
+                Action::Error(Error::FailedRequest.to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn api_url_can_be_set_at_runtime() {
+        std::env::set_var("TTYX_API_URL", " http://auth.test/ ");
+        assert_eq!(
+            AuthConfig::from_environment(),
+            Some(AuthConfig::new("http://auth.test/"))
+        );
+        std::env::set_var("TTYX_API_URL", "");
+        assert_eq!(AuthConfig::from_environment(), None);
+        std::env::remove_var("TTYX_API_URL");
+    }
+}
@@ -1,36 +1,103 @@
 //use crate::utils::inject_backend_footer;
-use super::fps;
+use super::{browser_window, fps};
 use ratzilla::backend::canvas::CanvasBackendOptions;
 use ratzilla::backend::dom::DomBackendOptions;
 use ratzilla::backend::webgl2::WebGl2BackendOptions;
 use ratzilla::ratatui::backend::Backend;
+#[cfg(any(test, feature = "headless"))]
+use ratzilla::ratatui::{backend::TestBackend, buffer::Buffer, layout::Size};
 use ratzilla::ratatui::{Terminal, TerminalOptions};
 use ratzilla::{CanvasBackend, DomBackend, WebGl2Backend};
 use std::convert::TryFrom;
 use std::fmt;
 use std::io;
-use web_sys::{window, Url};
+use wasm_bindgen::JsValue;
+use web_sys::Url;
 
 /// Available backend types
-#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum BackendType {
     #[default]
     Dom,
     Canvas,
     WebGl2,
+    /// In-memory buffer, for rendering without a browser
+    #[cfg(any(test, feature = "headless"))]
+    Headless,
 }
 
 impl BackendType {
+    pub const ALL: [BackendType; 3] = [BackendType::Dom, BackendType::Canvas, BackendType::WebGl2];
+
+    /// Name shown to the user
+    pub fn label(&self) -> &'static str {
+        match self {
+            BackendType::Dom => "DOM",
+            BackendType::Canvas => "Canvas",
+            BackendType::WebGl2 => "WebGL2",
+            #[cfg(any(test, feature = "headless"))]
+            BackendType::Headless => "Headless",
+        }
+    }
+
     /// Get the string representation
     pub fn as_str(&self) -> &'static str {
         match self {
             BackendType::Dom => "dom",
             BackendType::Canvas => "canvas",
             BackendType::WebGl2 => "webgl2",
+            #[cfg(any(test, feature = "headless"))]
+            BackendType::Headless => "headless",
         }
     }
 }
 
+/// Outcome of picking a backend, kept so the app can tell the user about a fallback.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackendSelection {
+    /// Backend asked for, from the URL or the builder's default
+    pub requested: BackendType,
+    /// Backend actually in use
+    pub chosen: BackendType,
+    /// Backends tried before `chosen`, with the reason each one failed
+    pub failures: Vec<(BackendType, String)>,
+}
+
+#[allow(dead_code)]
+impl BackendSelection {
+    /// Selection of a backend created on the first try.
+    pub fn direct(backend_type: BackendType) -> Self {
+        Self {
+            requested: backend_type,
+            chosen: backend_type,
+            failures: Vec::new(),
+        }
+    }
+
+    /// Whether a backend other than the requested one is in use.
+    pub fn fell_back(&self) -> bool {
+        self.chosen != self.requested
+    }
+
+    /// Message explaining the fallback, `None` when the requested backend is in use.
+    pub fn notice(&self) -> Option<String> {
+        if !self.fell_back() {
+            return None;
+        }
+        let reasons: Vec<String> = self
+            .failures
+            .iter()
+            .map(|(backend_type, reason)| format!("{}: {reason}", backend_type.label()))
+            .collect();
+        Some(format!(
+            "Using {} instead of {} ({})",
+            self.chosen.label(),
+            self.requested.label(),
+            reasons.join("; ")
+        ))
+    }
+}
+
 impl TryFrom<String> for BackendType {
     type Error = String;
 
@@ -63,10 +130,14 @@ impl fmt::Display for BackendType {
 /// - `Dom`: HTML DOM-based rendering with accessibility features
 /// - `Canvas`: Canvas 2D API rendering with full Unicode support  
 /// - `WebGl2`: GPU-accelerated rendering using WebGL2 and beamterm-renderer
+/// - `Headless`: in-memory buffer for native tests, with the `headless` feature
+#[allow(clippy::large_enum_variant)]
 pub enum RatzillaBackend {
     Dom(DomBackend),
     Canvas(CanvasBackend),
     WebGl2(WebGl2Backend),
+    #[cfg(any(test, feature = "headless"))]
+    Headless(TestBackend),
 }
 // disable unused warnings for now
 
@@ -78,6 +149,23 @@ impl RatzillaBackend {
             RatzillaBackend::Dom(_) => BackendType::Dom,
             RatzillaBackend::Canvas(_) => BackendType::Canvas,
             RatzillaBackend::WebGl2(_) => BackendType::WebGl2,
+            #[cfg(any(test, feature = "headless"))]
+            RatzillaBackend::Headless(_) => BackendType::Headless,
+        }
+    }
+
+    /// In-memory backend of `width` x `height` cells.
+    #[cfg(any(test, feature = "headless"))]
+    pub fn headless(width: u16, height: u16) -> Self {
+        RatzillaBackend::Headless(TestBackend::new(width, height))
+    }
+
+    /// What was last drawn, for the headless backend.
+    #[cfg(any(test, feature = "headless"))]
+    pub fn buffer(&self) -> Option<&Buffer> {
+        match self {
+            RatzillaBackend::Headless(backend) => Some(backend.buffer()),
+            _ => None,
         }
     }
 }
@@ -91,6 +179,8 @@ impl Backend for RatzillaBackend {
             RatzillaBackend::Dom(backend) => backend.draw(content),
             RatzillaBackend::Canvas(backend) => backend.draw(content),
             RatzillaBackend::WebGl2(backend) => backend.draw(content),
+            #[cfg(any(test, feature = "headless"))]
+            RatzillaBackend::Headless(backend) => backend.draw(content),
         }
     }
 
@@ -99,6 +189,8 @@ impl Backend for RatzillaBackend {
             RatzillaBackend::Dom(backend) => backend.flush(),
             RatzillaBackend::Canvas(backend) => backend.flush(),
             RatzillaBackend::WebGl2(backend) => backend.flush(),
+            #[cfg(any(test, feature = "headless"))]
+            RatzillaBackend::Headless(backend) => backend.flush(),
         }
     }
 
@@ -107,6 +199,8 @@ impl Backend for RatzillaBackend {
             RatzillaBackend::Dom(backend) => backend.size(),
             RatzillaBackend::Canvas(backend) => backend.size(),
             RatzillaBackend::WebGl2(backend) => backend.size(),
+            #[cfg(any(test, feature = "headless"))]
+            RatzillaBackend::Headless(backend) => backend.size(),
         }
     }
 
@@ -115,6 +209,8 @@ impl Backend for RatzillaBackend {
             RatzillaBackend::Dom(backend) => backend.hide_cursor(),
             RatzillaBackend::Canvas(backend) => backend.hide_cursor(),
             RatzillaBackend::WebGl2(backend) => backend.hide_cursor(),
+            #[cfg(any(test, feature = "headless"))]
+            RatzillaBackend::Headless(backend) => backend.hide_cursor(),
         }
     }
 
@@ -123,6 +219,8 @@ impl Backend for RatzillaBackend {
             RatzillaBackend::Dom(backend) => backend.show_cursor(),
             RatzillaBackend::Canvas(backend) => backend.show_cursor(),
             RatzillaBackend::WebGl2(backend) => backend.show_cursor(),
+            #[cfg(any(test, feature = "headless"))]
+            RatzillaBackend::Headless(backend) => backend.show_cursor(),
         }
     }
 
@@ -131,6 +229,8 @@ impl Backend for RatzillaBackend {
             RatzillaBackend::Dom(backend) => backend.get_cursor_position(),
             RatzillaBackend::Canvas(backend) => backend.get_cursor_position(),
             RatzillaBackend::WebGl2(backend) => backend.get_cursor_position(),
+            #[cfg(any(test, feature = "headless"))]
+            RatzillaBackend::Headless(backend) => backend.get_cursor_position(),
         }
     }
 
@@ -142,6 +242,8 @@ impl Backend for RatzillaBackend {
             RatzillaBackend::Dom(backend) => backend.set_cursor_position(position),
             RatzillaBackend::Canvas(backend) => backend.set_cursor_position(position),
             RatzillaBackend::WebGl2(backend) => backend.set_cursor_position(position),
+            #[cfg(any(test, feature = "headless"))]
+            RatzillaBackend::Headless(backend) => backend.set_cursor_position(position),
         }
     }
 
@@ -150,6 +252,8 @@ impl Backend for RatzillaBackend {
             RatzillaBackend::Dom(backend) => backend.clear(),
             RatzillaBackend::Canvas(backend) => backend.clear(),
             RatzillaBackend::WebGl2(backend) => backend.clear(),
+            #[cfg(any(test, feature = "headless"))]
+            RatzillaBackend::Headless(backend) => backend.clear(),
         }
     }
 
@@ -158,6 +262,8 @@ impl Backend for RatzillaBackend {
             RatzillaBackend::Dom(backend) => backend.append_lines(n),
             RatzillaBackend::Canvas(backend) => backend.append_lines(n),
             RatzillaBackend::WebGl2(backend) => backend.append_lines(n),
+            #[cfg(any(test, feature = "headless"))]
+            RatzillaBackend::Headless(backend) => backend.append_lines(n),
         }
     }
 
@@ -166,6 +272,8 @@ impl Backend for RatzillaBackend {
             RatzillaBackend::Dom(backend) => backend.window_size(),
             RatzillaBackend::Canvas(backend) => backend.window_size(),
             RatzillaBackend::WebGl2(backend) => backend.window_size(),
+            #[cfg(any(test, feature = "headless"))]
+            RatzillaBackend::Headless(backend) => backend.window_size(),
         }
     }
 }
@@ -177,6 +285,7 @@ impl Backend for RatzillaBackend {
 /// The FPS data can be accessed through the `fps` module functions.
 pub struct FpsTrackingBackend {
     inner: RatzillaBackend,
+    selection: BackendSelection,
 }
 
 #[allow(dead_code)]
@@ -185,13 +294,34 @@ impl FpsTrackingBackend {
     ///
     /// Frame timing will be recorded automatically on each successful flush operation.
     pub fn new(backend: RatzillaBackend) -> Self {
-        Self { inner: backend }
+        let selection = BackendSelection::direct(backend.backend_type());
+        Self {
+            inner: backend,
+            selection,
+        }
+    }
+
+    /// Record how the wrapped backend was picked.
+    pub fn with_selection(mut self, selection: BackendSelection) -> Self {
+        self.selection = selection;
+        self
     }
 
     /// Get the backend type for the wrapped backend.
     pub fn backend_type(&self) -> BackendType {
         self.inner.backend_type()
     }
+
+    /// What was last drawn, for the headless backend.
+    #[cfg(any(test, feature = "headless"))]
+    pub fn buffer(&self) -> Option<&Buffer> {
+        self.inner.buffer()
+    }
+
+    /// How the wrapped backend was picked, including the backends that failed before it.
+    pub fn selection(&self) -> &BackendSelection {
+        &self.selection
+    }
 }
 
 impl From<RatzillaBackend> for FpsTrackingBackend {
@@ -266,15 +396,20 @@ impl Backend for FpsTrackingBackend {
 /// 2. Fallback backend specified in `with_fallback()`
 /// 3. Default backend (DOM)
 ///
+/// If the selected backend can't be created, e.g. WebGL2 on a browser without it, the
+/// backends after it in the fallback chain are tried in turn (WebGL2 → Canvas → DOM
+/// unless set with `fallback_chain()`). `terminal.backend().selection()` tells which one
+/// was chosen and why the others failed.
+///
 /// # Example
 ///
 /// ```rust
-/// use examples_shared::backend::{BackendType, MultiBackendBuilder};
+/// use examples_shared::backend::{BackendType, FpsTrackingBackend, MultiBackendBuilder};
 /// use ratzilla::backend::canvas::CanvasBackendOptions;
 /// use ratzilla::backend::webgl2::WebGl2BackendOptions;
-/// use ratzilla::ratatui::TerminalOptions;
+/// use ratzilla::ratatui::{Terminal, TerminalOptions};
 ///
-/// let terminal = MultiBackendBuilder::with_fallback(BackendType::Dom)
+/// let terminal: Terminal<FpsTrackingBackend> = MultiBackendBuilder::with_fallback(BackendType::Dom)
 ///     .canvas_options(CanvasBackendOptions::new().grid_id("terminal-id"))
 ///     .webgl2_options(WebGl2BackendOptions::new().size((1200, 800)))
 ///     .build_terminal()?;
@@ -282,9 +417,11 @@ impl Backend for FpsTrackingBackend {
 /// // Get backend type if needed
 /// let backend_type = terminal.backend().backend_type();
 /// ```
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct MultiBackendBuilder {
     default_backend: BackendType,
+    backend: Option<BackendType>,
+    fallback_chain: Vec<BackendType>,
 
     terminal_options: TerminalOptions,
     canvas_options: CanvasBackendOptions,
@@ -292,7 +429,20 @@ pub struct MultiBackendBuilder {
     webgl2_options: WebGl2BackendOptions,
 }
 
-#[allow(dead_code)]
+impl Default for MultiBackendBuilder {
+    fn default() -> Self {
+        Self {
+            default_backend: BackendType::default(),
+            backend: None,
+            fallback_chain: DEFAULT_FALLBACK_CHAIN.to_vec(),
+            terminal_options: TerminalOptions::default(),
+            canvas_options: CanvasBackendOptions::default(),
+            dom_options: DomBackendOptions::default(),
+            webgl2_options: WebGl2BackendOptions::default(),
+        }
+    }
+}
+
 impl MultiBackendBuilder {
     /// Create a new builder with the specified fallback backend type.
     ///
@@ -304,9 +454,28 @@ impl MultiBackendBuilder {
         }
     }
 
+    /// Use `backend_type` regardless of the URL query parameters.
+    ///
+    /// Used when switching backends at runtime, where the choice comes from the user.
+    pub fn backend(mut self, backend_type: BackendType) -> Self {
+        self.backend = Some(backend_type);
+        self
+    }
+
+    /// Set the backends to fall back on, from the most to the least capable.
+    ///
+    /// When the selected backend fails, the ones after it in the chain are tried, or the
+    /// whole chain if it isn't part of it. An empty chain disables the fallback.
+    pub fn fallback_chain(mut self, chain: impl IntoIterator<Item = BackendType>) -> Self {
+        self.fallback_chain = chain.into_iter().collect();
+        self
+    }
+
     /// Set terminal configuration options.
     ///
     /// These options control terminal behavior such as viewport behavior and drawing settings.
+    // The app draws fullscreen with the defaults, but the builder covers every option
+    #[allow(dead_code)]
     pub fn terminal_options(mut self, options: TerminalOptions) -> Self {
         self.terminal_options = options;
         self
@@ -316,6 +485,8 @@ impl MultiBackendBuilder {
     ///
     /// These options control Canvas 2D rendering behavior such as font settings,
     /// cursor appearance, and Unicode support.
+    // The app's Canvas backend uses the defaults, but the builder covers every backend
+    #[allow(dead_code)]
     pub fn canvas_options(mut self, options: CanvasBackendOptions) -> Self {
         self.canvas_options = options;
         self
@@ -343,46 +514,70 @@ impl MultiBackendBuilder {
     ///
     /// This method:
     /// 1. Determines the backend type from URL query parameters or fallback
-    /// 2. Creates the appropriate backend with the configured options
-    /// 3. Wraps the backend with FPS tracking
+    /// 2. Creates the appropriate backend with the configured options, going down the
+    ///    fallback chain when that fails
+    /// 3. Wraps the backend with FPS tracking, recording how it was selected
     /// 4. Creates and returns the terminal with the selected backend
     /// 5. Injects a backend footer into the DOM (best effort)
     ///
     /// # Returns
     ///
-    /// The configured terminal instance. You can get the backend type using
-    /// `terminal.backend().backend_type()` if needed.
+    /// The configured terminal instance. Its backend is the `FpsTrackingBackend`, or a wrapper
+    /// around it such as `RecordingBackend<FpsTrackingBackend>`. You can get the backend type
+    /// using `terminal.backend().backend_type()` if needed.
     ///
     /// # Errors
     ///
-    /// Returns an error if backend creation or terminal initialization fails.
+    /// Returns an error if every backend of the chain fails, or terminal initialization fails.
     ///
     /// # Example
     ///
     /// ```rust
-    /// use examples_shared::backend::{BackendType, MultiBackendBuilder};
-    /// let terminal = MultiBackendBuilder::with_fallback(BackendType::Canvas)
-    ///     .build_terminal()?;
+    /// use examples_shared::backend::{BackendType, FpsTrackingBackend, MultiBackendBuilder};
+    /// use ratzilla::ratatui::Terminal;
+    ///
+    /// let terminal: Terminal<FpsTrackingBackend> =
+    ///     MultiBackendBuilder::with_fallback(BackendType::Canvas).build_terminal()?;
     ///
     /// // Get backend type if needed
     /// let backend_type = terminal.backend().backend_type();
     /// println!("Using {backend_type} backend");
     /// ```
-    pub fn build_terminal(self) -> io::Result<Terminal<FpsTrackingBackend>> {
-        let backend_type = parse_backend_from_url(self.default_backend);
-        let backend = create_backend_with_options(
-            backend_type,
-            Some(self.dom_options),
-            Some(self.canvas_options),
-            Some(self.webgl2_options),
-        )?;
+    pub fn build_terminal<B>(self) -> io::Result<Terminal<B>>
+    where
+        B: Backend + From<FpsTrackingBackend>,
+    {
+        let backend_type = self
+            .backend
+            .unwrap_or_else(|| parse_backend_from_url(self.default_backend));
+        // Each backend is tried at most once, so its options are only needed once
+        let mut dom_options = Some(self.dom_options);
+        let mut canvas_options = Some(self.canvas_options);
+        let mut webgl2_options = Some(self.webgl2_options);
+        let (backend, selection) =
+            select_backend(backend_type, &self.fallback_chain, |backend_type| {
+                let (dom, canvas, webgl2) = match backend_type {
+                    BackendType::Dom => (dom_options.take(), None, None),
+                    BackendType::Canvas => (None, canvas_options.take(), None),
+                    BackendType::WebGl2 => (None, None, webgl2_options.take()),
+                    #[cfg(any(test, feature = "headless"))]
+                    BackendType::Headless => (None, None, None),
+                };
+                // A backend may fail after adding its element, e.g. WebGL2 adds its canvas
+                // before finding out there is no context, so clear up for the next one
+                create_backend_with_options(backend_type, dom, canvas, webgl2)
+                    .inspect_err(|_| remove_backend_surfaces())
+            })?;
+        if let Some(notice) = selection.notice() {
+            log::warn!("{notice}");
+        }
 
         // Initialize FPS recorder
         fps::init_fps_recorder();
 
         // Wrap backend with FPS tracking
-        let fps_backend: FpsTrackingBackend = backend.into();
-        let terminal = Terminal::with_options(fps_backend, self.terminal_options)?;
+        let fps_backend = FpsTrackingBackend::from(backend).with_selection(selection);
+        let terminal = Terminal::with_options(fps_backend.into(), self.terminal_options)?;
 
         // // Inject footer (ignore errors)
         // let _ = inject_backend_footer(backend_type);
@@ -397,13 +592,84 @@ impl From<BackendType> for MultiBackendBuilder {
     }
 }
 
+/// Size of the headless backend built by `MultiBackendBuilder`.
+#[cfg(any(test, feature = "headless"))]
+pub const HEADLESS_SIZE: Size = Size::new(80, 24);
+
+/// Terminal drawing into memory, for rendering pages without a browser.
+#[cfg(any(test, feature = "headless"))]
+pub fn headless_terminal<B>(width: u16, height: u16) -> io::Result<Terminal<B>>
+where
+    B: Backend + From<FpsTrackingBackend>,
+{
+    Terminal::new(FpsTrackingBackend::from(RatzillaBackend::headless(width, height)).into())
+}
+
+/// Rows of `buffer` as text, without styles and trailing spaces.
+#[cfg(any(test, feature = "headless"))]
+pub fn buffer_lines(buffer: &Buffer) -> Vec<String> {
+    let area = buffer.area;
+    (area.top()..area.bottom())
+        .map(|y| {
+            let line: String = (area.left()..area.right())
+                .map(|x| buffer[(x, y)].symbol())
+                .collect();
+            line.trim_end().to_string()
+        })
+        .collect()
+}
+
+/// Backends tried when the selected one fails, from the most to the least capable.
+pub const DEFAULT_FALLBACK_CHAIN: [BackendType; 3] =
+    [BackendType::WebGl2, BackendType::Canvas, BackendType::Dom];
+
+/// Create the `requested` backend, or the first of the fallback chain that can be created.
+///
+/// Only the backends after `requested` in the chain are tried, so a failing Canvas falls
+/// back to DOM but never to WebGL2. Returns the error of every attempt when all fail.
+fn select_backend<B>(
+    requested: BackendType,
+    chain: &[BackendType],
+    mut create: impl FnMut(BackendType) -> io::Result<B>,
+) -> io::Result<(B, BackendSelection)> {
+    let rest = match chain.iter().position(|&b| b == requested) {
+        Some(i) => &chain[i + 1..],
+        None => chain,
+    };
+    let mut failures = Vec::new();
+    for backend_type in std::iter::once(requested).chain(rest.iter().copied()) {
+        if failures.iter().any(|(tried, _)| *tried == backend_type) {
+            continue;
+        }
+        match create(backend_type) {
+            Ok(backend) => {
+                let selection = BackendSelection {
+                    requested,
+                    chosen: backend_type,
+                    failures,
+                };
+                return Ok((backend, selection));
+            }
+            Err(err) => failures.push((backend_type, err.to_string())),
+        }
+    }
+    let reasons: Vec<String> = failures
+        .iter()
+        .map(|(backend_type, reason)| format!("{}: {reason}", backend_type.label()))
+        .collect();
+    Err(io::Error::other(format!(
+        "No backend could be created ({})",
+        reasons.join("; ")
+    )))
+}
+
 /// Parse the backend type from URL query parameters, with fallback to default.
 ///
 /// Checks for a `?backend=<type>` query parameter in the current page URL.
 /// Valid backend types are "dom", "canvas", and "webgl2" (case-insensitive).
 /// If no valid backend is found in the URL, returns the provided default.
 fn parse_backend_from_url(default: BackendType) -> BackendType {
-    window()
+    browser_window()
         .and_then(|w| w.location().href().ok())
         .and_then(|url| Url::new(url.as_str()).ok())
         .and_then(|url| url.search_params().get("backend"))
@@ -411,6 +677,45 @@ fn parse_backend_from_url(default: BackendType) -> BackendType {
         .unwrap_or(default)
 }
 
+/// Set `?backend=<type>` in the URL without reloading, so a reload keeps the backend.
+///
+/// Rewrites the current history entry, keeping its state.
+pub fn remember_backend_in_url(backend_type: BackendType) {
+    let Some(window) = browser_window() else {
+        return;
+    };
+    let (Ok(history), Ok(href)) = (window.history(), window.location().href()) else {
+        return;
+    };
+    let Ok(url) = Url::new(&href) else {
+        return;
+    };
+    url.search_params().set("backend", backend_type.as_str());
+    let state = history.state().unwrap_or(JsValue::NULL);
+    history
+        .replace_state_with_url(&state, "", Some(&url.href()))
+        .ok();
+}
+
+/// Remove what the backends drew into: the DOM backend's grid and the canvases of the
+/// Canvas and WebGL2 backends.
+///
+/// Backends don't clean up after themselves when dropped, so this has to happen before
+/// another one is created.
+pub fn remove_backend_surfaces() {
+    let Some(document) = browser_window().and_then(|w| w.document()) else {
+        return;
+    };
+    if let Some(grid) = document.get_element_by_id(&DomBackendOptions::default().grid_id()) {
+        grid.remove();
+    }
+    // The collection is live, removing an element shifts the next one to the front
+    let canvases = document.get_elements_by_tag_name("canvas");
+    while let Some(canvas) = canvases.item(0) {
+        canvas.remove();
+    }
+}
+
 /// Create a backend instance with the specified type and options.
 ///
 /// Creates the appropriate backend variant (DOM, Canvas, or WebGL2) using the provided
@@ -448,5 +753,81 @@ fn create_backend_with_options(
         BackendType::WebGl2 => Ok(WebGl2(WebGl2Backend::new_with_options(
             webgl2_options.unwrap_or_default(),
         )?)),
+        #[cfg(any(test, feature = "headless"))]
+        BackendType::Headless => Ok(RatzillaBackend::headless(
+            HEADLESS_SIZE.width,
+            HEADLESS_SIZE.height,
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates every backend but the ones in `broken`, returning the created type.
+    fn create(broken: &[BackendType]) -> impl FnMut(BackendType) -> io::Result<BackendType> + '_ {
+        move |backend_type| {
+            if broken.contains(&backend_type) {
+                Err(io::Error::other("not supported"))
+            } else {
+                Ok(backend_type)
+            }
+        }
+    }
+
+    #[test]
+    fn falls_back_down_the_chain() {
+        let (backend, selection) = select_backend(
+            BackendType::WebGl2,
+            &DEFAULT_FALLBACK_CHAIN,
+            create(&[BackendType::WebGl2]),
+        )
+        .unwrap();
+        assert_eq!(backend, BackendType::Canvas);
+        assert!(selection.fell_back());
+        assert_eq!(
+            selection.failures,
+            vec![(BackendType::WebGl2, "not supported".to_string())]
+        );
+        assert_eq!(
+            selection.notice().as_deref(),
+            Some("Using Canvas instead of WebGL2 (WebGL2: not supported)")
+        );
+
+        // Never up the chain
+        let (backend, _) = select_backend(
+            BackendType::Canvas,
+            &DEFAULT_FALLBACK_CHAIN,
+            create(&[BackendType::Canvas]),
+        )
+        .unwrap();
+        assert_eq!(backend, BackendType::Dom);
+    }
+
+    #[test]
+    fn requested_backend_is_used_when_it_works() {
+        let (backend, selection) =
+            select_backend(BackendType::Dom, &DEFAULT_FALLBACK_CHAIN, create(&[])).unwrap();
+        assert_eq!(backend, BackendType::Dom);
+        assert_eq!(selection, BackendSelection::direct(BackendType::Dom));
+        assert!(selection.notice().is_none());
+    }
+
+    #[test]
+    fn fails_when_the_chain_runs_out() {
+        let err = select_backend(BackendType::WebGl2, &[], create(&[BackendType::WebGl2]))
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "No backend could be created (WebGL2: not supported)"
+        );
+        assert!(select_backend(
+            BackendType::Canvas,
+            &DEFAULT_FALLBACK_CHAIN,
+            create(&BackendType::ALL)
+        )
+        .is_err());
     }
 }
@@ -0,0 +1,174 @@
+//! Parser of the `:` command line.
+//!
+//! A line is a command name and its arguments, separated by spaces. Arguments containing
+//! spaces are quoted, as in `toast "Build done" All tests passed`.
+use crate::app::Page;
+use crate::utils::{Action, BackendType, Command, Error, Theme};
+
+/// Commands of the command line and the arguments they take.
+const COMMANDS: &[(&str, &str)] = &[
+    ("page", "<page>"),
+    ("back", ""),
+    ("forward", ""),
+    ("backend", "<backend>"),
+    ("theme", "<theme>"),
+    ("toast", "<title> [body]"),
+    ("logout", ""),
+    ("clear-history", ""),
+    ("edit", ""),
+    ("help", ""),
+    ("palette", ""),
+    ("command-line", ""),
+    ("record", ""),
+];
+
+/// Parse a command line, without the leading `:`, into the action it stands for.
+pub fn parse_command_line(line: &str) -> Result<Action, String> {
+    let args = split_args(line)?;
+    let Some((name, args)) = args.split_first() else {
+        return Err("Type a command, Tab lists them".to_string());
+    };
+    let Some((_, params)) = COMMANDS.iter().find(|(command, _)| command == name) else {
+        return Err(format!("Unknown command '{name}'"));
+    };
+    let usage = || format!("Usage: {name} {params}").trim_end().to_string();
+    match (name.as_str(), args) {
+        ("toast", [title, body @ ..]) => Ok(Action::Toast(title.clone(), body.join(" "))),
+        ("toast", []) => Err(usage()),
+        (_, args) if args.len() != params.split_whitespace().count() => Err(usage()),
+        _ => {
+            // The arguments are there, so only their values can be wrong
+            let command: Command = format!("{name} {}", args.join(" "))
+                .parse()
+                .map_err(|err| match err {
+                    Error::Configuration(message) => message,
+                    err => err.to_string(),
+                })?;
+            Ok(command.action())
+        }
+    }
+}
+
+/// Lines the command line can be completed to, in the order Tab cycles through them.
+///
+/// The last word is completed, as a command name or as the command's argument.
+pub fn complete(line: &str) -> Vec<String> {
+    let start = line.rfind(' ').map(|i| i + 1).unwrap_or(0);
+    let (head, word) = line.split_at(start);
+    let candidates: Vec<String> = match head.split_whitespace().collect::<Vec<_>>()[..] {
+        [] => COMMANDS.iter().map(|(name, _)| name.to_string()).collect(),
+        ["page"] => Page::ALL.iter().map(|p| p.as_str().to_string()).collect(),
+        ["backend"] => BackendType::ALL
+            .iter()
+            .map(|b| b.as_str().to_string())
+            .collect(),
+        ["theme"] => Theme::ALL.iter().map(|t| t.as_str().to_string()).collect(),
+        _ => Vec::new(),
+    };
+    candidates
+        .into_iter()
+        .filter(|candidate| candidate.starts_with(word) && candidate != word)
+        .map(|candidate| format!("{head}{candidate}"))
+        .collect()
+}
+
+/// Split a line at spaces, keeping the spaces of quoted arguments.
+fn split_args(line: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current: Option<String> = None;
+    let mut quoted = false;
+    for c in line.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                current.get_or_insert_with(String::new);
+            }
+            c if c.is_whitespace() && !quoted => args.extend(current.take()),
+            c => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quoted {
+        return Err("Missing closing quote".to_string());
+    }
+    args.extend(current);
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_map_to_actions() {
+        assert_eq!(
+            parse_command_line("page settings"),
+            Ok(Action::ChangePage(Page::Settings))
+        );
+        assert_eq!(
+            parse_command_line(" backend  webgl2 "),
+            Ok(Action::SetBackend(BackendType::WebGl2))
+        );
+        assert_eq!(
+            parse_command_line("theme light"),
+            Ok(Action::SetTheme(Theme::Light))
+        );
+        assert_eq!(parse_command_line("logout"), Ok(Action::Logout));
+        assert_eq!(
+            parse_command_line(r#"toast "Build done" all  tests passed"#),
+            Ok(Action::Toast(
+                "Build done".to_string(),
+                "all tests passed".to_string()
+            ))
+        );
+        assert_eq!(
+            parse_command_line(r#"toast """#),
+            Ok(Action::Toast(String::new(), String::new()))
+        );
+    }
+
+    #[test]
+    fn arguments_are_validated() {
+        assert_eq!(
+            parse_command_line("page"),
+            Err("Usage: page <page>".to_string())
+        );
+        assert_eq!(
+            parse_command_line("page nowhere"),
+            Err(
+                "Invalid page: 'nowhere'. Valid options are: login, verify, home, settings, help"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            parse_command_line("logout now"),
+            Err("Usage: logout".to_string())
+        );
+        assert_eq!(
+            parse_command_line("toast"),
+            Err("Usage: toast <title> [body]".to_string())
+        );
+        assert_eq!(
+            parse_command_line("dance"),
+            Err("Unknown command 'dance'".to_string())
+        );
+        assert!(parse_command_line(r#"toast "open"#).is_err());
+        assert!(parse_command_line("").is_err());
+    }
+
+    #[test]
+    fn every_command_can_be_typed() {
+        for command in Command::all() {
+            let line = command.to_string();
+            assert_eq!(parse_command_line(&line), Ok(command.action()), "{line}");
+        }
+    }
+
+    #[test]
+    fn completes_names_and_arguments() {
+        assert_eq!(complete("pa"), vec!["page", "palette"]);
+        assert_eq!(complete("page s"), vec!["page settings"]);
+        assert_eq!(complete("theme "), vec!["theme dark", "theme light"]);
+        assert!(complete("page settings").is_empty());
+        assert!(complete("toast hi ").is_empty());
+    }
+}
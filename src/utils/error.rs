@@ -10,13 +10,12 @@
 //Once ready to add Actix Error types that can be returned from the API, add the following to the top of the file
 // use actix_web::HttpResponse;
 
-use std::io::ErrorKind;
-
 use color_eyre::eyre::Report;
 use serde::Serialize;
 
 pub type Result<T> = core::result::Result<T, Error>;
 
+#[allow(dead_code)]
 #[derive(Debug)]
 pub enum Error {
     AppFail,
@@ -107,8 +106,12 @@ pub enum Error {
     InvalidAppDataPath,
     FailedRequest,
     ActionSender(String),
+    Clipboard(String),
+    KeymapConflict(String),
+    Recording(String),
 }
 
+#[allow(dead_code)]
 #[derive(Serialize)]
 pub struct ErrorMessage {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -120,7 +123,7 @@ pub struct ErrorMessage {
 
 impl From<Error> for std::io::Error {
     fn from(value: Error) -> Self {
-        std::io::Error::new(ErrorKind::Other, value)
+        std::io::Error::other(value)
     }
 }
 
@@ -0,0 +1,93 @@
+use ratzilla::ratatui::layout::{Position, Rect};
+
+/// A widget that can take focus, where it was last drawn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FocusArea {
+    /// Name of the widget within its page, sent back in `Action::Focus`
+    pub id: &'static str,
+    pub area: Rect,
+    pub focused: bool,
+}
+
+impl FocusArea {
+    pub fn new(id: &'static str, area: Rect, focused: bool) -> Self {
+        Self { id, area, focused }
+    }
+}
+
+/// Focusable widgets of the page on screen, in Tab order.
+///
+/// The page reports them after every draw, so the areas always match what is on screen
+/// and the page stays the one deciding what has focus.
+#[derive(Debug, Default)]
+pub struct FocusRing {
+    areas: Vec<FocusArea>,
+}
+
+impl FocusRing {
+    pub fn set(&mut self, areas: Vec<FocusArea>) {
+        self.areas = areas;
+    }
+
+    /// Widget after the focused one, or before it going backwards, wrapping around.
+    ///
+    /// Without a focused widget this is the first one, or the last going backwards.
+    pub fn cycle(&self, forward: bool) -> Option<&'static str> {
+        let len = self.areas.len();
+        if len == 0 {
+            return None;
+        }
+        let current = self.areas.iter().position(|area| area.focused);
+        let index = match (current, forward) {
+            (Some(i), true) => (i + 1) % len,
+            (Some(i), false) => (i + len - 1) % len,
+            (None, true) => 0,
+            (None, false) => len - 1,
+        };
+        Some(self.areas[index].id)
+    }
+
+    /// Widget under `position`, the last drawn one if they overlap.
+    pub fn hit(&self, position: Position) -> Option<&'static str> {
+        self.areas
+            .iter()
+            .rev()
+            .find(|area| area.area.contains(position))
+            .map(|area| area.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring(focused: Option<usize>) -> FocusRing {
+        let mut ring = FocusRing::default();
+        ring.set(
+            ["a", "b", "c"]
+                .into_iter()
+                .enumerate()
+                .map(|(i, id)| {
+                    FocusArea::new(id, Rect::new(0, i as u16 * 3, 10, 3), focused == Some(i))
+                })
+                .collect(),
+        );
+        ring
+    }
+
+    #[test]
+    fn tab_wraps_around() {
+        assert_eq!(ring(None).cycle(true), Some("a"));
+        assert_eq!(ring(None).cycle(false), Some("c"));
+        assert_eq!(ring(Some(2)).cycle(true), Some("a"));
+        assert_eq!(ring(Some(0)).cycle(false), Some("c"));
+        assert_eq!(FocusRing::default().cycle(true), None);
+    }
+
+    #[test]
+    fn clicks_hit_the_widget_under_them() {
+        let ring = ring(None);
+        assert_eq!(ring.hit(Position::new(5, 4)), Some("b"));
+        assert_eq!(ring.hit(Position::new(11, 4)), None);
+    }
+}
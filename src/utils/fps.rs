@@ -1,7 +1,7 @@
+use super::browser_window;
 use std::cell::RefCell;
 use std::thread_local;
 use wasm_bindgen::JsValue;
-use web_sys::window;
 use web_time::Instant;
 
 thread_local! {
@@ -97,7 +97,7 @@ pub fn get_current_fps() -> f32 {
 /// Update the FPS display in the footer
 fn update_fps_display(fps: f32) {
     let exps = || -> Result<(), JsValue> {
-        let fps_element = window()
+        let fps_element = browser_window()
             .and_then(|w| w.document())
             .and_then(|d| d.get_element_by_id("ratzilla-fps"));
 
@@ -0,0 +1,787 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+use ratzilla::event::{KeyCode, KeyEvent};
+use serde::Deserialize;
+
+use crate::app::Page;
+use crate::pages::components::{EntryKind, Modal, PaletteEntry};
+use crate::utils::{browser_window, Action, BackendType, Error, Result, Theme};
+
+/// Bindings shipped with the app, the user's bindings are layered on top.
+const DEFAULT_KEYMAP: &str = include_str!("keymap.json");
+
+/// Local storage key of the user's bindings, in the same format as `keymap.json`.
+pub const KEYMAP_STORAGE_KEY: &str = "ttyx.keymap";
+
+/// A key with its modifiers, written like `ctrl+k`, `shift+tab`, `C` or `space`.
+///
+/// Shift is part of the character for printable keys, so `C` and `shift+c` are the same
+/// chord and match what the browser reports for Shift+C.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+}
+
+impl From<&KeyEvent> for KeyChord {
+    fn from(key: &KeyEvent) -> Self {
+        Self {
+            code: key.code.clone(),
+            ctrl: key.ctrl,
+            alt: key.alt,
+            shift: key.shift && !matches!(key.code, KeyCode::Char(_)),
+        }
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (mut ctrl, mut alt, mut shift) = (false, false, false);
+        let mut key = s.trim();
+        // The key itself may be `+`, as in `ctrl++`
+        while let Some((modifier, rest)) = key.split_once('+').filter(|(_, rest)| !rest.is_empty())
+        {
+            match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => ctrl = true,
+                "alt" | "meta" => alt = true,
+                "shift" => shift = true,
+                _ => return Err(Error::InvalidKeyEvent(format!("Unknown modifier in '{s}'"))),
+            }
+            key = rest;
+        }
+        let code = parse_key_code(key)
+            .ok_or_else(|| Error::InvalidKeyEvent(format!("Unknown key in '{s}'")))?;
+        Ok(match code {
+            KeyCode::Char(c) if shift => Self {
+                code: KeyCode::Char(c.to_ascii_uppercase()),
+                ctrl,
+                alt,
+                shift: false,
+            },
+            code => Self {
+                code,
+                ctrl,
+                alt,
+                shift,
+            },
+        })
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "ctrl+")?;
+        }
+        if self.alt {
+            write!(f, "alt+")?;
+        }
+        if self.shift {
+            write!(f, "shift+")?;
+        }
+        match &self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::F(n) => write!(f, "f{n}"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Left => write!(f, "left"),
+            KeyCode::Right => write!(f, "right"),
+            KeyCode::Up => write!(f, "up"),
+            KeyCode::Down => write!(f, "down"),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::Delete => write!(f, "delete"),
+            KeyCode::Home => write!(f, "home"),
+            KeyCode::End => write!(f, "end"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::Unidentified => write!(f, "unidentified"),
+        }
+    }
+}
+
+fn parse_key_code(key: &str) -> Option<KeyCode> {
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+    let key = key.to_lowercase();
+    Some(match key.as_str() {
+        "space" => KeyCode::Char(' '),
+        "backspace" => KeyCode::Backspace,
+        "enter" | "return" => KeyCode::Enter,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "tab" => KeyCode::Tab,
+        "delete" | "del" => KeyCode::Delete,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "esc" | "escape" => KeyCode::Esc,
+        _ => KeyCode::F(key.strip_prefix('f')?.parse().ok().filter(|n| *n > 0)?),
+    })
+}
+
+/// Chords pressed one after the other, written separated by spaces like `g g`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeySequence(pub Vec<KeyChord>);
+
+impl KeySequence {
+    /// Whether this sequence begins with `keys`, or is equal to it.
+    pub fn starts_with(&self, keys: &[KeyChord]) -> bool {
+        self.0.starts_with(keys)
+    }
+
+    /// Whether one of the sequences is typed on the way to the other, so both can't be bound.
+    fn overlaps(&self, other: &KeySequence) -> bool {
+        self.starts_with(&other.0) || other.starts_with(&self.0)
+    }
+}
+
+impl FromStr for KeySequence {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let chords = s
+            .split_whitespace()
+            .map(KeyChord::from_str)
+            .collect::<Result<Vec<_>>>()?;
+        if chords.is_empty() {
+            return Err(Error::InvalidKeyEvent("Empty key sequence".to_string()));
+        }
+        Ok(Self(chords))
+    }
+}
+
+impl fmt::Display for KeySequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, chord) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{chord}")?;
+        }
+        Ok(())
+    }
+}
+
+/// What a key can be bound to, written like `page settings` or `logout` in the keymap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Page(Page),
+    Back,
+    Forward,
+    Logout,
+    ClearHistory,
+    /// Start editing the page's input
+    Edit,
+    Help,
+    Palette,
+    /// The `:` command line
+    Prompt,
+    /// Start or stop recording the session
+    Record,
+    /// Render with another backend
+    Backend(BackendType),
+    Theme(Theme),
+}
+
+impl Command {
+    /// Every command, pages first.
+    pub fn all() -> Vec<Command> {
+        let mut commands: Vec<Command> = Page::ALL.into_iter().map(Command::Page).collect();
+        commands.extend([
+            Command::Back,
+            Command::Forward,
+            Command::Logout,
+            Command::ClearHistory,
+            Command::Edit,
+            Command::Help,
+            Command::Palette,
+            Command::Prompt,
+            Command::Record,
+        ]);
+        commands.extend(BackendType::ALL.into_iter().map(Command::Backend));
+        commands.extend(Theme::ALL.into_iter().map(Command::Theme));
+        commands
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            Command::Page(Page::Help) => "Show the help".to_string(),
+            Command::Page(page) => format!("Go to the {page} page"),
+            Command::Back => "Go back a page".to_string(),
+            Command::Forward => "Go forward a page".to_string(),
+            Command::Logout => "Log out".to_string(),
+            Command::ClearHistory => "Forget the pages visited so far".to_string(),
+            Command::Edit => "Start editing".to_string(),
+            Command::Help => "Show or hide this help".to_string(),
+            Command::Palette => "Open the command palette".to_string(),
+            Command::Prompt => "Type a command".to_string(),
+            Command::Record => "Start or stop recording the session".to_string(),
+            Command::Backend(backend) => format!("Render with {}", backend.label()),
+            Command::Theme(theme) => format!("Use the {theme} theme"),
+        }
+    }
+
+    pub fn action(&self) -> Action {
+        match self {
+            Command::Page(page) => Action::ChangePage(*page),
+            Command::Back => Action::GoBack,
+            Command::Forward => Action::GoForward,
+            Command::Logout => Action::Logout,
+            Command::ClearHistory => Action::ShowModal(Modal::confirm(
+                "Clear history",
+                "Forget the pages visited so far?",
+                Action::ClearHistory,
+            )),
+            Command::Edit => Action::EnterInput,
+            Command::Help => Action::ToggleShowHelp,
+            Command::Palette => Action::TogglePalette,
+            Command::Prompt => Action::OpenCommandLine,
+            Command::Record => Action::ToggleRecording,
+            Command::Backend(backend) => Action::SetBackend(*backend),
+            Command::Theme(theme) => Action::SetTheme(*theme),
+        }
+    }
+}
+
+impl FromStr for Command {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut words = s.split_whitespace();
+        let command = match (words.next(), words.next()) {
+            (Some("page"), Some(page)) => {
+                Command::Page(Page::try_from(page.to_string()).map_err(Error::Configuration)?)
+            }
+            (Some("back"), None) => Command::Back,
+            (Some("forward"), None) => Command::Forward,
+            (Some("logout"), None) => Command::Logout,
+            (Some("clear-history"), None) => Command::ClearHistory,
+            (Some("edit"), None) => Command::Edit,
+            (Some("help"), None) => Command::Help,
+            (Some("palette"), None) => Command::Palette,
+            (Some("command-line"), None) => Command::Prompt,
+            (Some("record"), None) => Command::Record,
+            (Some("theme"), Some(theme)) => {
+                Command::Theme(Theme::try_from(theme.to_string()).map_err(Error::Configuration)?)
+            }
+            (Some("backend"), Some(backend)) => Command::Backend(
+                BackendType::try_from(backend.to_string()).map_err(Error::Configuration)?,
+            ),
+            _ => return Err(Error::Configuration(format!("Unknown command '{s}'"))),
+        };
+        match words.next() {
+            Some(_) => Err(Error::Configuration(format!("Unknown command '{s}'"))),
+            None => Ok(command),
+        }
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::Page(page) => write!(f, "page {page}"),
+            Command::Back => write!(f, "back"),
+            Command::Forward => write!(f, "forward"),
+            Command::Logout => write!(f, "logout"),
+            Command::ClearHistory => write!(f, "clear-history"),
+            Command::Edit => write!(f, "edit"),
+            Command::Help => write!(f, "help"),
+            Command::Palette => write!(f, "palette"),
+            Command::Prompt => write!(f, "command-line"),
+            Command::Record => write!(f, "record"),
+            Command::Backend(backend) => write!(f, "backend {backend}"),
+            Command::Theme(theme) => write!(f, "theme {theme}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binding {
+    pub keys: KeySequence,
+    pub command: Command,
+}
+
+/// Bindings listed under a title in the help, as keys and what they do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HelpSection {
+    pub title: String,
+    pub entries: Vec<(String, String)>,
+}
+
+/// Outcome of a key press for the keymap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyResult {
+    /// A binding was completed
+    Action(Action),
+    /// The key started or continued a sequence, more keys are needed
+    Pending,
+    /// Nothing is bound to the key
+    Unbound,
+}
+
+/// The keymap file, command by key sequence for every page plus the global ones.
+///
+/// An empty command removes a binding of an earlier layer.
+#[derive(Debug, Default, Deserialize)]
+struct KeymapFile {
+    #[serde(default)]
+    global: BTreeMap<String, String>,
+    #[serde(default)]
+    pages: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// Key bindings, global and per page.
+///
+/// Page bindings take precedence over a global binding of the same keys. A sequence can't
+/// be bound next to one it is the start of, like `g` and `g g`, as the longer one could
+/// never be typed.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    global: Vec<Binding>,
+    pages: HashMap<Page, Vec<Binding>>,
+    /// Keys of a sequence typed so far
+    pending: Vec<KeyChord>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::from_json(DEFAULT_KEYMAP).expect("Built-in keymap is valid")
+    }
+}
+
+impl Keymap {
+    /// A keymap without any bindings.
+    pub fn empty() -> Self {
+        Self {
+            global: Vec::new(),
+            pages: HashMap::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// The built-in bindings with the user's from local storage on top.
+    ///
+    /// Bindings that fail to load are logged and the built-in ones are used instead.
+    pub fn load() -> Self {
+        let mut keymap = Self::default();
+        let user = browser_window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .and_then(|storage| storage.get_item(KEYMAP_STORAGE_KEY).ok().flatten());
+        if let Some(json) = user {
+            let mut custom = keymap.clone();
+            match custom.extend_json(&json) {
+                Ok(()) => keymap = custom,
+                Err(err) => log::error!("Ignoring keymap from {}: {}", KEYMAP_STORAGE_KEY, err),
+            }
+        }
+        keymap
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        let mut keymap = Self::empty();
+        keymap.extend_json(json)?;
+        Ok(keymap)
+    }
+
+    /// Layer the bindings of a keymap file on top, replacing bindings of the same keys.
+    pub fn extend_json(&mut self, json: &str) -> Result<()> {
+        let file: KeymapFile = serde_json::from_str(json)?;
+        let mut entries = Vec::new();
+        for (keys, command) in file.global {
+            entries.push((None, keys, command));
+        }
+        for (page, bindings) in file.pages {
+            let page = Page::try_from(page).map_err(Error::Configuration)?;
+            for (keys, command) in bindings {
+                entries.push((Some(page), keys, command));
+            }
+        }
+        // Replace first, so a file can rebind keys without tripping over the old bindings
+        for (page, keys, _) in &entries {
+            self.unbind(*page, keys)?;
+        }
+        for (page, keys, command) in entries {
+            if !command.trim().is_empty() {
+                self.bind(page, &keys, &command)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Bind `keys` to `command` globally, or on `page` only.
+    pub fn bind(&mut self, page: Option<Page>, keys: &str, command: &str) -> Result<()> {
+        let binding = Binding {
+            keys: keys.parse()?,
+            command: command.parse()?,
+        };
+        let scope = |page: Option<Page>| match page {
+            Some(page) => format!("on the {page} page"),
+            None => "globally".to_string(),
+        };
+        let conflict = |other: &Binding, other_page: Option<Page>| {
+            Error::KeymapConflict(format!(
+                "'{}' ({}) {} conflicts with '{}' ({}) {}",
+                binding.keys,
+                binding.command,
+                scope(page),
+                other.keys,
+                other.command,
+                scope(other_page)
+            ))
+        };
+        if let Some(other) = self
+            .bindings(page)
+            .iter()
+            .find(|other| other.keys.overlaps(&binding.keys))
+        {
+            return Err(conflict(other, page));
+        }
+        // A page binding may override a global one, but not hide it behind a prefix
+        let others: Vec<(Option<Page>, &Binding)> = match page {
+            Some(_) => self.global.iter().map(|other| (None, other)).collect(),
+            None => self
+                .pages
+                .iter()
+                .flat_map(|(page, bindings)| bindings.iter().map(|other| (Some(*page), other)))
+                .collect(),
+        };
+        if let Some((other_page, other)) = others
+            .into_iter()
+            .find(|(_, other)| other.keys != binding.keys && other.keys.overlaps(&binding.keys))
+        {
+            return Err(conflict(other, other_page));
+        }
+
+        match page {
+            Some(page) => self.pages.entry(page).or_default().push(binding),
+            None => self.global.push(binding),
+        }
+        Ok(())
+    }
+
+    /// Remove the binding of `keys`, returning whether there was one.
+    pub fn unbind(&mut self, page: Option<Page>, keys: &str) -> Result<bool> {
+        let keys: KeySequence = keys.parse()?;
+        let bindings = match page {
+            Some(page) => self.pages.entry(page).or_default(),
+            None => &mut self.global,
+        };
+        let len = bindings.len();
+        bindings.retain(|binding| binding.keys != keys);
+        Ok(bindings.len() != len)
+    }
+
+    /// Bindings of `page`, or the global ones.
+    pub fn bindings(&self, page: Option<Page>) -> &[Binding] {
+        match page {
+            Some(page) => self.pages.get(&page).map(Vec::as_slice).unwrap_or_default(),
+            None => &self.global,
+        }
+    }
+
+    /// Bindings active on `page`, its own first and then the global ones it doesn't override.
+    pub fn help(&self, page: Page) -> Vec<HelpSection> {
+        let own = self.bindings(Some(page));
+        let section = |title: String, bindings: Vec<&Binding>| HelpSection {
+            title,
+            entries: bindings
+                .into_iter()
+                .map(|binding| (binding.keys.to_string(), binding.command.description()))
+                .collect(),
+        };
+        let mut sections = Vec::new();
+        if !own.is_empty() {
+            let title = format!("{} page", capitalize(page.as_str()));
+            sections.push(section(title, own.iter().collect()));
+        }
+        let global = self
+            .global
+            .iter()
+            .filter(|binding| own.iter().all(|other| other.keys != binding.keys))
+            .collect();
+        sections.push(section("Global".to_string(), global));
+        sections
+    }
+
+    /// Keys that run `command` on `page`, if it is bound there or globally.
+    pub fn keys_for(&self, page: Page, command: Command) -> Option<&KeySequence> {
+        let own = self.bindings(Some(page));
+        own.iter()
+            .chain(
+                self.global
+                    .iter()
+                    .filter(|binding| own.iter().all(|other| other.keys != binding.keys)),
+            )
+            .find(|binding| binding.command == command)
+            .map(|binding| &binding.keys)
+    }
+
+    /// Entries of the command palette on `page`, with the keys of each command.
+    ///
+    /// Editing is only offered on pages that bind it, as the others have nothing to edit.
+    pub fn palette(&self, page: Page) -> Vec<PaletteEntry> {
+        Command::all()
+            .into_iter()
+            .filter(|command| *command != Command::Palette)
+            .filter(|command| {
+                *command != Command::Edit
+                    || self
+                        .bindings(Some(page))
+                        .iter()
+                        .any(|binding| binding.command == Command::Edit)
+            })
+            .map(|command| PaletteEntry {
+                kind: match command {
+                    Command::Page(_) => EntryKind::Page,
+                    Command::Backend(_) | Command::Theme(_) => EntryKind::Setting,
+                    _ => EntryKind::Command,
+                },
+                title: command.description(),
+                keys: self.keys_for(page, command).map(ToString::to_string),
+                action: command.action(),
+            })
+            .collect()
+    }
+
+    /// Forget the keys of an unfinished sequence.
+    pub fn reset(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Feed a key press on `page` to the keymap.
+    ///
+    /// A key that doesn't continue the pending sequence starts a new one.
+    pub fn handle_key(&mut self, page: Page, key: &KeyEvent) -> KeyResult {
+        self.pending.push(KeyChord::from(key));
+        let scopes = [self.bindings(Some(page)), self.bindings(None)];
+        let exact = scopes
+            .iter()
+            .flat_map(|bindings| bindings.iter())
+            .find(|binding| binding.keys.0 == self.pending)
+            .map(|binding| binding.command);
+        let prefix = scopes
+            .iter()
+            .flat_map(|bindings| bindings.iter())
+            .any(|binding| binding.keys.starts_with(&self.pending));
+        if let Some(command) = exact {
+            self.pending.clear();
+            return KeyResult::Action(command.action());
+        }
+        if prefix {
+            return KeyResult::Pending;
+        }
+        let retry = self.pending.len() > 1;
+        self.pending.clear();
+        if retry {
+            return self.handle_key(page, key);
+        }
+        KeyResult::Unbound
+    }
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(c: char) -> KeyEvent {
+        KeyEvent {
+            code: KeyCode::Char(c),
+            ctrl: false,
+            alt: false,
+            shift: c.is_ascii_uppercase(),
+        }
+    }
+
+    #[test]
+    fn default_keymap_loads() {
+        let keymap = Keymap::default();
+        assert!(!keymap.bindings(None).is_empty());
+        assert!(!keymap.bindings(Some(Page::Login)).is_empty());
+    }
+
+    #[test]
+    fn chords_parse_and_print() {
+        let chord: KeyChord = "ctrl+k".parse().unwrap();
+        assert_eq!(chord.code, KeyCode::Char('k'));
+        assert!(chord.ctrl);
+        assert_eq!("shift+c".parse::<KeyChord>().unwrap(), "C".parse().unwrap());
+        assert_eq!(
+            "ctrl++".parse::<KeyChord>().unwrap().code,
+            KeyCode::Char('+')
+        );
+        assert_eq!(
+            "Shift+Tab".parse::<KeyChord>().unwrap().to_string(),
+            "shift+tab"
+        );
+        assert_eq!("f5".parse::<KeyChord>().unwrap().code, KeyCode::F(5));
+        assert!("hyper+x".parse::<KeyChord>().is_err());
+        assert!("nope".parse::<KeyChord>().is_err());
+        assert_eq!("g  g".parse::<KeySequence>().unwrap().to_string(), "g g");
+    }
+
+    #[test]
+    fn sequences_wait_for_the_next_key() {
+        let mut keymap = Keymap::empty();
+        keymap.bind(None, "g g", "page login").unwrap();
+        keymap.bind(None, "x", "logout").unwrap();
+        assert_eq!(keymap.handle_key(Page::Help, &key('g')), KeyResult::Pending);
+        assert_eq!(
+            keymap.handle_key(Page::Help, &key('g')),
+            KeyResult::Action(Action::ChangePage(Page::Login))
+        );
+        // A key that breaks the sequence is handled on its own
+        keymap.handle_key(Page::Help, &key('g'));
+        assert_eq!(
+            keymap.handle_key(Page::Help, &key('x')),
+            KeyResult::Action(Action::Logout)
+        );
+        assert_eq!(keymap.handle_key(Page::Help, &key('y')), KeyResult::Unbound);
+    }
+
+    #[test]
+    fn page_bindings_override_global_ones() {
+        let mut keymap = Keymap::empty();
+        keymap.bind(None, "e", "logout").unwrap();
+        keymap.bind(Some(Page::Login), "e", "edit").unwrap();
+        assert_eq!(
+            keymap.handle_key(Page::Login, &key('e')),
+            KeyResult::Action(Action::EnterInput)
+        );
+        assert_eq!(
+            keymap.handle_key(Page::Help, &key('e')),
+            KeyResult::Action(Action::Logout)
+        );
+    }
+
+    #[test]
+    fn shifted_and_modified_keys() {
+        let mut keymap = Keymap::empty();
+        keymap.bind(None, "C", "back").unwrap();
+        keymap.bind(None, "ctrl+c", "forward").unwrap();
+        assert_eq!(
+            keymap.handle_key(Page::Help, &key('C')),
+            KeyResult::Action(Action::GoBack)
+        );
+        let ctrl_c = KeyEvent {
+            ctrl: true,
+            ..key('c')
+        };
+        assert_eq!(
+            keymap.handle_key(Page::Help, &ctrl_c),
+            KeyResult::Action(Action::GoForward)
+        );
+        assert_eq!(keymap.handle_key(Page::Help, &key('c')), KeyResult::Unbound);
+    }
+
+    #[test]
+    fn conflicts_are_rejected() {
+        let mut keymap = Keymap::empty();
+        keymap.bind(None, "g g", "page login").unwrap();
+        assert!(keymap.bind(None, "g g", "logout").is_err());
+        assert!(keymap.bind(None, "g", "logout").is_err());
+        assert!(keymap.bind(None, "g g x", "logout").is_err());
+        assert!(keymap.bind(Some(Page::Help), "g", "logout").is_err());
+        keymap.bind(Some(Page::Help), "g g", "logout").unwrap();
+        assert!(keymap.bind(None, "x", "dance").is_err());
+    }
+
+    #[test]
+    fn help_lists_page_bindings_before_global_ones() {
+        let mut keymap = Keymap::empty();
+        keymap.bind(None, "e", "logout").unwrap();
+        keymap.bind(None, "?", "help").unwrap();
+        keymap.bind(Some(Page::Login), "e", "edit").unwrap();
+        let help = keymap.help(Page::Login);
+        assert_eq!(help[0].title, "Login page");
+        assert_eq!(
+            help[0].entries,
+            vec![("e".to_string(), "Start editing".to_string())]
+        );
+        assert_eq!(help[1].title, "Global");
+        assert_eq!(
+            help[1].entries,
+            vec![("?".to_string(), "Show or hide this help".to_string())]
+        );
+        assert_eq!(keymap.help(Page::Help).len(), 1);
+    }
+
+    #[test]
+    fn commands_round_trip() {
+        for command in Command::all() {
+            assert_eq!(command.to_string().parse::<Command>().unwrap(), command);
+        }
+        assert!("page nowhere".parse::<Command>().is_err());
+        assert!("logout now".parse::<Command>().is_err());
+    }
+
+    #[test]
+    fn palette_shows_the_keys_of_each_entry() {
+        let mut keymap = Keymap::empty();
+        keymap.bind(None, "ctrl+k", "palette").unwrap();
+        keymap.bind(None, "g s", "page settings").unwrap();
+        keymap.bind(Some(Page::Login), "e", "edit").unwrap();
+        let palette = keymap.palette(Page::Help);
+        assert!(palette
+            .iter()
+            .all(|entry| entry.action != Action::TogglePalette));
+        assert!(palette
+            .iter()
+            .all(|entry| entry.action != Action::EnterInput));
+        let settings = palette
+            .iter()
+            .find(|entry| entry.action == Action::ChangePage(Page::Settings))
+            .unwrap();
+        assert_eq!(settings.kind, EntryKind::Page);
+        assert_eq!(settings.keys.as_deref(), Some("g s"));
+        assert!(palette
+            .iter()
+            .any(|entry| entry.kind == EntryKind::Setting && entry.keys.is_none()));
+        assert!(keymap
+            .palette(Page::Login)
+            .iter()
+            .any(|entry| entry.action == Action::EnterInput));
+    }
+
+    #[test]
+    fn user_keymap_is_layered_on_top() {
+        let mut keymap = Keymap::default();
+        keymap
+            .extend_json(
+                r#"{"global": {"q": "page help", "o": ""}, "pages": {"help": {"x": "back"}}}"#,
+            )
+            .unwrap();
+        assert_eq!(
+            keymap.handle_key(Page::Help, &key('q')),
+            KeyResult::Action(Action::ChangePage(Page::Help))
+        );
+        assert_eq!(keymap.handle_key(Page::Help, &key('o')), KeyResult::Unbound);
+        assert_eq!(
+            keymap.handle_key(Page::Help, &key('x')),
+            KeyResult::Action(Action::GoBack)
+        );
+        assert!(keymap
+            .extend_json(r#"{"global": {"g": "logout"}}"#)
+            .is_err());
+    }
+}
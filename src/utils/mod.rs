@@ -1,9 +1,34 @@
 mod action;
+pub mod auth;
 mod backend;
+mod cmdline;
 mod error;
+mod focus;
 mod fps;
+mod keymap;
+mod navigation;
+mod recording;
+mod route;
+pub mod session;
+#[cfg(test)]
+mod snapshot;
+mod surface;
+mod task;
+mod tauri;
+mod theme;
+mod web;
 pub use action::*;
 pub use backend::*;
+pub use cmdline::*;
 pub use error::*;
-pub use fps::*;
-
+pub use focus::*;
+pub use keymap::*;
+pub use navigation::*;
+pub use recording::*;
+pub use route::*;
+#[cfg(test)]
+pub use snapshot::*;
+pub use surface::*;
+pub use task::*;
+pub use theme::*;
+pub use web::*;
@@ -0,0 +1,117 @@
+use std::collections::VecDeque;
+
+use crate::app::Page;
+
+/// Number of pages remembered in each direction.
+pub const HISTORY_LIMIT: usize = 50;
+
+/// Back/forward stacks of visited pages, like a browser's session history.
+///
+/// The current page is not stored here, callers pass it in so the stacks never disagree
+/// with what is on screen.
+#[derive(Debug, Clone)]
+pub struct Navigation {
+    back: VecDeque<Page>,
+    forward: Vec<Page>,
+    limit: usize,
+}
+
+impl Default for Navigation {
+    fn default() -> Self {
+        Self::new(HISTORY_LIMIT)
+    }
+}
+
+impl Navigation {
+    /// Create an empty history keeping at most `limit` pages in each direction.
+    pub fn new(limit: usize) -> Self {
+        Self {
+            back: VecDeque::with_capacity(limit),
+            forward: Vec::new(),
+            limit: limit.max(1),
+        }
+    }
+
+    /// Record that `from` was left for a new page, dropping the forward stack.
+    pub fn visit(&mut self, from: Page) {
+        self.forward.clear();
+        self.push_back(from);
+    }
+
+    /// Step back from `current`, returning the page to show.
+    pub fn back(&mut self, current: Page) -> Option<Page> {
+        let page = self.back.pop_back()?;
+        self.forward.push(current);
+        self.forward.truncate(self.limit);
+        Some(page)
+    }
+
+    /// Step forward from `current`, returning the page to show.
+    pub fn forward(&mut self, current: Page) -> Option<Page> {
+        let page = self.forward.pop()?;
+        self.push_back(current);
+        Some(page)
+    }
+
+    /// Forget every page in both directions.
+    pub fn clear(&mut self) {
+        self.back.clear();
+        self.forward.clear();
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        !self.back.is_empty()
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        !self.forward.is_empty()
+    }
+
+    fn push_back(&mut self, page: Page) {
+        if self.back.len() == self.limit {
+            self.back.pop_front();
+        }
+        self.back.push_back(page);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn back_and_forward() {
+        let mut nav = Navigation::default();
+        nav.visit(Page::Login);
+        nav.visit(Page::Settings);
+        // Now on Help
+        assert_eq!(nav.back(Page::Help), Some(Page::Settings));
+        assert_eq!(nav.back(Page::Settings), Some(Page::Login));
+        assert_eq!(nav.back(Page::Login), None);
+        assert_eq!(nav.forward(Page::Login), Some(Page::Settings));
+        assert_eq!(nav.forward(Page::Settings), Some(Page::Help));
+        assert_eq!(nav.forward(Page::Help), None);
+    }
+
+    #[test]
+    fn visiting_drops_forward_pages() {
+        let mut nav = Navigation::default();
+        nav.visit(Page::Login);
+        assert_eq!(nav.back(Page::Settings), Some(Page::Login));
+        assert!(nav.can_go_forward());
+        nav.visit(Page::Login);
+        assert!(!nav.can_go_forward());
+        assert_eq!(nav.back(Page::Help), Some(Page::Login));
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut nav = Navigation::new(2);
+        nav.visit(Page::Login);
+        nav.visit(Page::Verify);
+        nav.visit(Page::Settings);
+        assert_eq!(nav.back(Page::Help), Some(Page::Settings));
+        assert_eq!(nav.back(Page::Settings), Some(Page::Verify));
+        assert_eq!(nav.back(Page::Verify), None);
+    }
+}
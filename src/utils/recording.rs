@@ -0,0 +1,470 @@
+//! Session recordings in the asciicast v2 format, for bug reports and demos.
+//!
+//! [`RecordingBackend`] hands every cell it draws to its [`Recorder`], which turns them into
+//! ANSI escape sequences, one output event per flushed frame. Recordings start with a
+//! full redraw, so they don't depend on what was on screen before.
+//!
+//! See <https://docs.asciinema.org/manual/asciicast/v2/> for the format.
+use std::fmt::Write;
+use std::io;
+
+use ratzilla::ratatui::backend::{Backend, WindowSize};
+use ratzilla::ratatui::buffer::Cell;
+use ratzilla::ratatui::layout::{Position, Size};
+use ratzilla::ratatui::style::{Color, Modifier};
+use serde_json::json;
+use unicode_width::UnicodeWidthStr;
+use wasm_bindgen::prelude::*;
+use web_sys::js_sys::{Array, Object, Reflect};
+use web_time::{Instant, SystemTime, UNIX_EPOCH};
+
+use super::{browser_window, tauri, Action, Error, Result};
+use crate::pages::components::Severity;
+
+/// Time the browser gets to start a download before its object URL is revoked.
+const REVOKE_DELAY_MS: i32 = 60_000;
+
+/// An event of the recording, at seconds since it started.
+#[derive(Debug, Clone, PartialEq)]
+enum Event {
+    /// Text written to the terminal
+    Output(f64, String),
+    /// Terminal resized to columns x rows
+    Resize(f64, Size),
+}
+
+/// Records drawn frames as terminal output.
+#[derive(Debug)]
+pub struct Recorder {
+    /// Seconds since the Unix epoch at the start, for the header
+    timestamp: u64,
+    /// Set by the first full redraw, nothing is recorded before it
+    started: Option<(Instant, Size)>,
+    size: Size,
+    events: Vec<Event>,
+    /// Output of the frame being drawn
+    pending: String,
+    /// Where the terminal's cursor is after `pending`
+    cursor: Option<(u16, u16)>,
+    /// Style set by the last escape sequence of `pending`
+    style: Option<(Color, Color, Modifier)>,
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or_default();
+        Self {
+            timestamp,
+            started: None,
+            size: Size::default(),
+            events: Vec::new(),
+            pending: String::new(),
+            cursor: None,
+            style: None,
+        }
+    }
+
+    /// Whether the recording has seen its first full redraw.
+    pub fn is_started(&self) -> bool {
+        self.started.is_some()
+    }
+
+    /// Seconds since the recording started.
+    fn elapsed(&self) -> f64 {
+        self.started
+            .map(|(start, _)| start.elapsed().as_secs_f64())
+            .unwrap_or_default()
+    }
+
+    /// The terminal was cleared, so the next frame is drawn in full.
+    pub fn clear(&mut self, size: Size) {
+        if self.started.is_none() {
+            self.started = Some((Instant::now(), size));
+            self.size = size;
+        }
+        // Hide the cursor, reset the style, clear the screen
+        self.pending.push_str("\x1b[?25l\x1b[0m\x1b[2J");
+        self.cursor = None;
+        self.style = None;
+    }
+
+    /// Write the cells drawn since the last flush.
+    pub fn draw<'a>(&mut self, cells: impl IntoIterator<Item = (u16, u16, &'a Cell)>) {
+        if !self.is_started() {
+            return;
+        }
+        for (x, y, cell) in cells {
+            let symbol = cell.symbol();
+            // The second half of a wide character, drawn with the first
+            if symbol.is_empty() {
+                continue;
+            }
+            if self.cursor != Some((x, y)) {
+                write!(self.pending, "\x1b[{};{}H", y + 1, x + 1).ok();
+            }
+            let style = (cell.fg, cell.bg, cell.modifier);
+            if self.style != Some(style) {
+                self.pending.push_str(&sgr(cell.fg, cell.bg, cell.modifier));
+                self.style = Some(style);
+            }
+            self.pending.push_str(symbol);
+            self.cursor = Some((x + symbol.width().max(1) as u16, y));
+        }
+    }
+
+    /// End the frame, recording what was drawn as one event.
+    pub fn flush(&mut self, size: Size) {
+        if !self.is_started() {
+            return;
+        }
+        let time = self.elapsed();
+        if size != self.size {
+            self.size = size;
+            self.events.push(Event::Resize(time, size));
+        }
+        if !self.pending.is_empty() {
+            let output = std::mem::take(&mut self.pending);
+            self.events.push(Event::Output(time, output));
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// File name of the recording, with the time it started.
+    pub fn file_name(&self) -> String {
+        format!("{}-{}.cast", crate::APP_NAME, self.timestamp)
+    }
+
+    /// The recording as an asciicast v2 file: a JSON header line, then one line per event.
+    pub fn to_cast(&self) -> String {
+        let size = self.started.map(|(_, size)| size).unwrap_or(self.size);
+        let header = json!({
+            "version": 2,
+            "width": size.width,
+            "height": size.height,
+            "timestamp": self.timestamp,
+            "title": crate::APP_NAME,
+            "env": { "TERM": "xterm-256color" },
+        });
+        let mut cast = header.to_string();
+        cast.push('\n');
+        for event in &self.events {
+            let line = match event {
+                Event::Output(time, output) => json!([round(*time), "o", output]),
+                Event::Resize(time, size) => {
+                    json!([round(*time), "r", format!("{}x{}", size.width, size.height)])
+                }
+            };
+            cast.push_str(&line.to_string());
+            cast.push('\n');
+        }
+        cast
+    }
+}
+
+/// Keep timings to the microsecond, like asciinema does.
+fn round(seconds: f64) -> f64 {
+    (seconds * 1_000_000.0).round() / 1_000_000.0
+}
+
+/// Escape sequence setting the colors and modifiers of a cell, from a reset style.
+fn sgr(fg: Color, bg: Color, modifier: Modifier) -> String {
+    let mut params = vec!["0".to_string()];
+    for (flag, param) in [
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+        (Modifier::SLOW_BLINK, "5"),
+        (Modifier::RAPID_BLINK, "6"),
+        (Modifier::REVERSED, "7"),
+        (Modifier::HIDDEN, "8"),
+        (Modifier::CROSSED_OUT, "9"),
+    ] {
+        if modifier.contains(flag) {
+            params.push(param.to_string());
+        }
+    }
+    params.extend(color_param(fg, false));
+    params.extend(color_param(bg, true));
+    format!("\x1b[{}m", params.join(";"))
+}
+
+/// SGR parameter of a foreground or background color, `None` for the default color.
+fn color_param(color: Color, background: bool) -> Option<String> {
+    let offset = if background { 10 } else { 0 };
+    let base = match color {
+        Color::Reset => return None,
+        Color::Black => 30,
+        Color::Red => 31,
+        Color::Green => 32,
+        Color::Yellow => 33,
+        Color::Blue => 34,
+        Color::Magenta => 35,
+        Color::Cyan => 36,
+        Color::Gray => 37,
+        Color::DarkGray => 90,
+        Color::LightRed => 91,
+        Color::LightGreen => 92,
+        Color::LightYellow => 93,
+        Color::LightBlue => 94,
+        Color::LightMagenta => 95,
+        Color::LightCyan => 96,
+        Color::White => 97,
+        Color::Indexed(i) => return Some(format!("{};5;{i}", 38 + offset)),
+        Color::Rgb(r, g, b) => return Some(format!("{};2;{r};{g};{b}", 38 + offset)),
+    };
+    Some((base + offset).to_string())
+}
+
+/// Backend that records what it draws while a [`Recorder`] is installed.
+pub struct RecordingBackend<B: Backend> {
+    inner: B,
+    recorder: Option<Recorder>,
+}
+
+impl<B: Backend> RecordingBackend<B> {
+    pub fn new(inner: B) -> Self {
+        Self {
+            inner,
+            recorder: None,
+        }
+    }
+
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Record from the next clear of the terminal on, replacing any recording in progress.
+    pub fn install(&mut self, recorder: Recorder) {
+        self.recorder = Some(recorder);
+    }
+
+    /// Stop recording, returning what was recorded.
+    pub fn remove(&mut self) -> Option<Recorder> {
+        self.recorder.take()
+    }
+}
+
+impl<B: Backend> From<B> for RecordingBackend<B> {
+    fn from(backend: B) -> Self {
+        Self::new(backend)
+    }
+}
+
+impl<B: Backend> Backend for RecordingBackend<B> {
+    fn draw<'a, I>(&mut self, content: I) -> io::Result<()>
+    where
+        I: Iterator<Item = (u16, u16, &'a Cell)>,
+    {
+        let Some(recorder) = self.recorder.as_mut() else {
+            return self.inner.draw(content);
+        };
+        let cells: Vec<_> = content.collect();
+        recorder.draw(cells.iter().copied());
+        self.inner.draw(cells.into_iter())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()?;
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.flush(self.inner.size()?);
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> io::Result<Size> {
+        self.inner.size()
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        self.inner.hide_cursor()
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        self.inner.show_cursor()
+    }
+
+    fn get_cursor_position(&mut self) -> io::Result<Position> {
+        self.inner.get_cursor_position()
+    }
+
+    fn set_cursor_position<P: Into<Position>>(&mut self, position: P) -> io::Result<()> {
+        self.inner.set_cursor_position(position)
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.inner.clear()?;
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.clear(self.inner.size()?);
+        }
+        Ok(())
+    }
+
+    fn append_lines(&mut self, n: u16) -> io::Result<()> {
+        self.inner.append_lines(n)
+    }
+
+    fn window_size(&mut self) -> io::Result<WindowSize> {
+        self.inner.window_size()
+    }
+}
+
+/// Save a recording, resolving to a notification of where it went.
+///
+/// The Tauri shell writes it to the downloads directory, the browser downloads it.
+pub async fn save_recording(name: String, cast: String) -> Action {
+    let saved = if tauri::is_available() {
+        save_with_tauri(&name, cast).await
+    } else {
+        download(&name, &cast).map(|()| name)
+    };
+    match saved {
+        Ok(location) => Action::Notify(
+            Severity::Success,
+            "Recording saved".to_string(),
+            format!("Saved to {location}"),
+        ),
+        Err(err) => Action::Notify(
+            Severity::Error,
+            "Recording not saved".to_string(),
+            err.to_string(),
+        ),
+    }
+}
+
+/// Write the recording to disk through the Tauri shell, returning its path.
+async fn save_with_tauri(name: &str, cast: String) -> Result<String> {
+    let args = Object::new();
+    Reflect::set(&args, &"name".into(), &name.into()).ok();
+    Reflect::set(&args, &"contents".into(), &cast.into()).ok();
+    let path = tauri::invoke("save_recording", args.into())
+        .await
+        .map_err(|err| Error::Recording(format!("{err:?}")))?;
+    Ok(path.as_string().unwrap_or_else(|| name.to_string()))
+}
+
+/// Let the browser download `contents` as a file called `name`.
+fn download(name: &str, contents: &str) -> Result<()> {
+    let (window, document) = browser_window()
+        .and_then(|window| window.document().map(|document| (window, document)))
+        .ok_or_else(|| Error::Recording("No browser to download to".to_string()))?;
+    let js_error = |err: JsValue| Error::Recording(format!("{err:?}"));
+    let parts = Array::of1(&JsValue::from_str(contents));
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type("application/x-asciicast");
+    let blob =
+        web_sys::Blob::new_with_str_sequence_and_options(&parts, &options).map_err(js_error)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob).map_err(js_error)?;
+    let link = document.create_element("a").map_err(js_error)?;
+    link.set_attribute("href", &url).map_err(js_error)?;
+    link.set_attribute("download", name).map_err(js_error)?;
+    if let Ok(link) = link.dyn_into::<web_sys::HtmlElement>() {
+        link.click();
+    }
+    // The download starts after the click returns, revoking the URL now could cancel it
+    let revoke = Closure::once_into_js(move || {
+        web_sys::Url::revoke_object_url(&url).ok();
+    });
+    window
+        .set_timeout_with_callback_and_timeout_and_arguments_0(
+            revoke.unchecked_ref(),
+            REVOKE_DELAY_MS,
+        )
+        .map_err(js_error)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratzilla::ratatui::backend::TestBackend;
+    use ratzilla::ratatui::style::{Style, Stylize};
+
+    fn cell(symbol: &'static str, style: Style) -> Cell {
+        let mut cell = Cell::new(symbol);
+        cell.set_style(style);
+        cell
+    }
+
+    #[test]
+    fn styles_map_to_escape_sequences() {
+        assert_eq!(
+            sgr(Color::Reset, Color::Reset, Modifier::empty()),
+            "\x1b[0m"
+        );
+        assert_eq!(
+            sgr(Color::Yellow, Color::Black, Modifier::BOLD),
+            "\x1b[0;1;33;40m"
+        );
+        assert_eq!(
+            sgr(Color::Rgb(1, 2, 3), Color::Indexed(200), Modifier::empty()),
+            "\x1b[0;38;2;1;2;3;48;5;200m"
+        );
+    }
+
+    #[test]
+    fn frames_become_output_events() {
+        let size = Size::new(10, 2);
+        let mut recorder = Recorder::new();
+        let plain = cell("a", Style::default());
+        // Nothing before the first full redraw
+        recorder.draw([(0, 0, &plain)]);
+        recorder.flush(size);
+        assert!(recorder.is_empty());
+
+        recorder.clear(size);
+        let bold = cell("c", Style::default().bold());
+        recorder.draw([(0, 0, &plain), (1, 0, &plain), (5, 1, &bold)]);
+        recorder.flush(size);
+        recorder.flush(Size::new(20, 4));
+
+        let cast = recorder.to_cast();
+        let lines: Vec<serde_json::Value> = cast
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["version"], 2);
+        assert_eq!(lines[0]["width"], 10);
+        assert_eq!(lines[0]["height"], 2);
+        assert_eq!(lines[1][1], "o");
+        assert_eq!(
+            lines[1][2],
+            "\x1b[?25l\x1b[0m\x1b[2J\x1b[1;1H\x1b[0maa\x1b[2;6H\x1b[0;1mc"
+        );
+        assert_eq!(lines[2][1], "r");
+        assert_eq!(lines[2][2], "20x4");
+    }
+
+    #[test]
+    fn backend_records_while_a_recorder_is_installed() {
+        let mut backend = RecordingBackend::new(TestBackend::new(4, 1));
+        let plain = cell("a", Style::default());
+        backend.draw([(0, 0, &plain)].into_iter()).unwrap();
+        backend.flush().unwrap();
+
+        backend.install(Recorder::new());
+        backend.clear().unwrap();
+        backend.draw([(1, 0, &plain)].into_iter()).unwrap();
+        backend.flush().unwrap();
+
+        let recorder = backend.remove().unwrap();
+        assert!(backend.remove().is_none());
+        let cast = recorder.to_cast();
+        let output: serde_json::Value = serde_json::from_str(cast.lines().nth(1).unwrap()).unwrap();
+        assert_eq!(output[2], "\x1b[?25l\x1b[0m\x1b[2J\x1b[1;2H\x1b[0ma");
+        assert_eq!(cast.lines().count(), 2);
+    }
+}
@@ -0,0 +1,149 @@
+//! Keeps the URL fragment in sync with the current [`Page`].
+//!
+//! Every navigation pushes a history entry whose state is its position in the session's
+//! history, so `popstate` can tell whether the browser went back or forward.
+use std::cell::Cell;
+use std::thread_local;
+use tokio::sync::mpsc::UnboundedSender;
+use wasm_bindgen::prelude::*;
+
+use super::{browser_window, Action};
+use crate::app::Page;
+
+thread_local! {
+    /// Position of the current history entry as last seen by the app, picking up where a
+    /// reloaded page left off
+    static HISTORY_INDEX: Cell<u32> = Cell::new(current_state().unwrap_or(0));
+}
+
+/// Index stored in the current history entry, if the app created it.
+fn current_state() -> Option<u32> {
+    browser_window()
+        .and_then(|w| w.history().ok())
+        .and_then(|h| h.state().ok())
+        .and_then(|state| state.as_f64())
+        .map(|index| index as u32)
+}
+
+/// Parse the page from the URL fragment, with fallback to default.
+///
+/// Checks for a `#/<page>` fragment in the current page URL. If no valid page is found,
+/// returns the provided default.
+pub fn parse_page_from_url(default: Page) -> Page {
+    browser_window()
+        .and_then(|w| w.location().hash().ok())
+        .and_then(|hash| Page::try_from(hash).ok())
+        .unwrap_or(default)
+}
+
+/// Point the URL at `page`.
+///
+/// Pushes a new history entry, unless the URL already shows the page because the browser
+/// navigated by itself. Entries the app didn't create (initial load, a fragment typed into the
+/// address bar) are rewritten and tagged with the next index instead of being pushed over.
+pub fn push_page(page: Page) {
+    let Some(window) = browser_window() else {
+        return;
+    };
+    let Ok(history) = window.history() else {
+        return;
+    };
+    let path = page.path();
+    let is_current = window.location().hash().ok().as_deref() == Some(path.as_str());
+    match current_state() {
+        Some(index) if is_current => HISTORY_INDEX.with(|i| i.set(index)),
+        state => {
+            let index = HISTORY_INDEX.with(|i| {
+                i.set(i.get() + 1);
+                i.get()
+            });
+            let index = JsValue::from(index);
+            if state.is_some() {
+                history.push_state_with_url(&index, "", Some(&path)).ok();
+            } else {
+                history.replace_state_with_url(&index, "", Some(&path)).ok();
+            }
+        }
+    }
+}
+
+/// Point the current history entry at `page` instead of pushing a new one.
+///
+/// For when the browser moved through its history to a page that can't be shown, e.g. one
+/// that needs a session. Pushing would drop the entries ahead of the current one, so the
+/// entry is rewritten and keeps its position.
+pub fn replace_page(page: Page) {
+    let Some(history) = browser_window().and_then(|w| w.history().ok()) else {
+        return;
+    };
+    let index = current_state().unwrap_or_else(|| HISTORY_INDEX.with(|i| i.get() + 1));
+    HISTORY_INDEX.with(|i| i.set(index));
+    history
+        .replace_state_with_url(&JsValue::from(index), "", Some(&page.path()))
+        .ok();
+}
+
+/// Ask the browser to go back, returning `false` outside the browser.
+///
+/// The resulting `popstate` arrives as `Action::Back`, which keeps the browser's history and
+/// the app's in step.
+pub fn go_back() -> bool {
+    browser_window()
+        .and_then(|w| w.history().ok())
+        .is_some_and(|history| history.back().is_ok())
+}
+
+/// Ask the browser to go forward, returning `false` outside the browser.
+pub fn go_forward() -> bool {
+    browser_window()
+        .and_then(|w| w.history().ok())
+        .is_some_and(|history| history.forward().is_ok())
+}
+
+/// Forward browser navigation as actions.
+///
+/// - `popstate` on an entry created by [`push_page`] becomes `Action::Back` or
+///   `Action::Forward` depending on its position relative to the current entry.
+/// - `hashchange` to a fragment typed into the address bar becomes `Action::ChangePage`.
+pub fn on_route_event(tx: UnboundedSender<Action>) {
+    let Some(window) = browser_window() else {
+        return;
+    };
+    let popstate = Closure::<dyn FnMut(_)>::new({
+        let tx = tx.clone();
+        move |event: web_sys::PopStateEvent| {
+            // Entries the app didn't create are picked up by `hashchange`
+            let Some(index) = event.state().as_f64().map(|index| index as u32) else {
+                return;
+            };
+            let current = HISTORY_INDEX.with(|i| i.replace(index));
+            let action = if index < current {
+                Action::Back
+            } else {
+                Action::Forward
+            };
+            tx.send(action).ok();
+        }
+    });
+    let hashchange = Closure::<dyn FnMut(_)>::new(move |_: web_sys::HashChangeEvent| {
+        // Entries created by the app are handled by `popstate`
+        let state = browser_window()
+            .and_then(|w| w.history().ok())
+            .and_then(|h| h.state().ok());
+        if state.is_some_and(|state| state.as_f64().is_some()) {
+            return;
+        }
+        let hash = browser_window().and_then(|w| w.location().hash().ok());
+        if let Some(page) = hash.and_then(|hash| Page::try_from(hash).ok()) {
+            tx.send(Action::ChangePage(page)).ok();
+        }
+    });
+    window
+        .add_event_listener_with_callback("popstate", popstate.as_ref().unchecked_ref())
+        .ok();
+    window
+        .add_event_listener_with_callback("hashchange", hashchange.as_ref().unchecked_ref())
+        .ok();
+    popstate.forget();
+    hashchange.forget();
+}
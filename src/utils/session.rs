@@ -0,0 +1,322 @@
+//! Persistence and validation of the logged in user's session.
+//!
+//! The token is kept in `localStorage` when running in a browser and in a file owned by the
+//! Tauri shell when running as a desktop app. Native builds (tests, tooling) don't persist
+//! anything unless `TTYX_SESSION_FILE` points at a file.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use web_time::{SystemTime, UNIX_EPOCH};
+
+use super::{browser_window, Action, Error, Result};
+
+/// Key of the session in `localStorage`.
+pub const STORAGE_KEY: &str = "ttyx.session";
+
+/// Authenticated session returned by the verify endpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    /// Bearer token for subsequent API calls.
+    pub token: String,
+    /// Expiry as seconds since the Unix epoch, if the server provides one.
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct Claims {
+    exp: Option<u64>,
+}
+
+impl Session {
+    /// Expiry of the session in seconds since the Unix epoch.
+    ///
+    /// Uses `expires_at` from the server when present and otherwise falls back to the `exp`
+    /// claim of a JWT. Opaque tokens without an expiry never expire on the client side.
+    pub fn expiry(&self) -> Result<Option<u64>> {
+        if self.token.trim().is_empty() {
+            return Err(Error::InvalidToken);
+        }
+        if self.expires_at.is_some() {
+            return Ok(self.expires_at);
+        }
+        let parts: Vec<&str> = self.token.split('.').collect();
+        let [_, payload, _] = parts.as_slice() else {
+            return Ok(None);
+        };
+        let payload = URL_SAFE_NO_PAD
+            .decode(payload.trim_end_matches('='))
+            .map_err(|_| Error::InvalidToken)?;
+        let claims: Claims =
+            serde_json::from_slice(&payload).map_err(|_| Error::FailedToCheckToken)?;
+        Ok(claims.exp)
+    }
+
+    /// Make sure the session can still be used at `now` (seconds since the Unix epoch).
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidToken` if the token is empty or not a well-formed JWT
+    /// - `FailedToCheckToken` if the JWT claims can't be read
+    /// - `ExpiredToken` if the expiry has passed
+    pub fn check_at(&self, now: u64) -> Result<()> {
+        check_expiry(self.expiry()?, now)
+    }
+
+    /// Make sure the session can still be used right now.
+    pub fn check(&self) -> Result<()> {
+        self.check_at(now()?)
+    }
+}
+
+/// Make sure a session expiring at `expiry`, as returned by [`Session::expiry`], can still
+/// be used at `now`.
+///
+/// Cheaper than [`Session::check_at`] for checks that run often, as the token isn't read.
+pub fn check_expiry(expiry: Option<u64>, now: u64) -> Result<()> {
+    match expiry {
+        Some(expiry) if expiry <= now => Err(Error::ExpiredToken),
+        _ => Ok(()),
+    }
+}
+
+/// Seconds since the Unix epoch.
+pub fn now() -> Result<u64> {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .map_err(|_| Error::FailedToCheckToken)
+}
+
+/// Where the session is persisted between visits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionStore {
+    /// `localStorage` of the current origin.
+    LocalStorage,
+    /// A file in the app data directory, managed by the Tauri shell's commands.
+    Tauri,
+    /// A file on disk.
+    File(PathBuf),
+    /// Nothing is persisted.
+    Ephemeral,
+}
+
+impl SessionStore {
+    /// Pick the store for the environment the app is running in.
+    pub fn detect() -> Self {
+        if crate::utils::tauri::is_available() {
+            SessionStore::Tauri
+        } else if browser_window().is_some() {
+            SessionStore::LocalStorage
+        } else {
+            std::env::var_os("TTYX_SESSION_FILE")
+                .map(|path| SessionStore::File(path.into()))
+                .unwrap_or(SessionStore::Ephemeral)
+        }
+    }
+
+    /// Read the stored session.
+    ///
+    /// The Tauri store can only be read asynchronously, so it always returns `Ok(None)` here;
+    /// use [`SessionStore::restore`] for it instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TokenCouldNotBeRead` if the storage can't be accessed or holds garbage.
+    pub fn load(&self) -> Result<Option<Session>> {
+        let raw = match self {
+            SessionStore::LocalStorage => local_storage()?
+                .get_item(STORAGE_KEY)
+                .map_err(|_| Error::TokenCouldNotBeRead)?,
+            SessionStore::File(path) => match std::fs::read_to_string(path) {
+                Ok(raw) => Some(raw),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+                Err(_) => return Err(Error::TokenCouldNotBeRead),
+            },
+            SessionStore::Tauri | SessionStore::Ephemeral => None,
+        };
+        raw.map(|raw| parse(&raw)).transpose()
+    }
+
+    /// Whether the store must be read with [`SessionStore::restore`].
+    pub fn is_async(&self) -> bool {
+        *self == SessionStore::Tauri
+    }
+
+    /// Read the stored session, resolving to `Action::StartSession` if it is still valid.
+    pub async fn restore(self) -> Option<Action> {
+        let session = match self {
+            SessionStore::Tauri => tauri::load().await,
+            _ => self.load(),
+        };
+        match session {
+            Ok(Some(session)) if session.check().is_ok() => {
+                return Some(Action::StartSession(session));
+            }
+            Ok(Some(_)) => self.clear().unwrap_or_else(log_error),
+            Ok(None) => {}
+            Err(err) => log_error(err),
+        }
+        None
+    }
+
+    /// Persist the session, replacing any previous one.
+    pub fn save(&self, session: &Session) -> Result<()> {
+        let raw = serde_json::to_string(session)?;
+        match self {
+            SessionStore::LocalStorage => local_storage()?
+                .set_item(STORAGE_KEY, &raw)
+                .map_err(|_| Error::StoreFailToCreate(STORAGE_KEY.to_string())),
+            SessionStore::File(path) => {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                Ok(std::fs::write(path, raw)?)
+            }
+            SessionStore::Tauri => {
+                tauri::spawn_command("save_session", Some(raw));
+                Ok(())
+            }
+            SessionStore::Ephemeral => Ok(()),
+        }
+    }
+
+    /// Forget the stored session.
+    pub fn clear(&self) -> Result<()> {
+        match self {
+            SessionStore::LocalStorage => local_storage()?
+                .remove_item(STORAGE_KEY)
+                .map_err(|_| Error::StoreFailedToDelete(STORAGE_KEY.to_string())),
+            SessionStore::File(path) => match std::fs::remove_file(path) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+                _ => Ok(()),
+            },
+            SessionStore::Tauri => {
+                tauri::spawn_command("clear_session", None);
+                Ok(())
+            }
+            SessionStore::Ephemeral => Ok(()),
+        }
+    }
+}
+
+fn parse(raw: &str) -> Result<Session> {
+    serde_json::from_str(raw).map_err(|_| Error::TokenCouldNotBeRead)
+}
+
+fn log_error(err: Error) {
+    log::error!("Session store: {}", err);
+}
+
+fn local_storage() -> Result<web_sys::Storage> {
+    browser_window()
+        .and_then(|w| w.local_storage().ok().flatten())
+        .ok_or(Error::TokenCouldNotBeRead)
+}
+
+/// Access to the session commands exposed by the Tauri shell (`src-tauri`).
+mod tauri {
+    use super::{log_error, parse, Error, Result, Session};
+    use crate::utils::tauri::invoke;
+    use wasm_bindgen::JsValue;
+    use web_sys::js_sys::{Object, Reflect};
+
+    pub async fn load() -> Result<Option<Session>> {
+        let raw = invoke("load_session", JsValue::UNDEFINED)
+            .await
+            .map_err(|_| Error::TokenCouldNotBeRead)?;
+        raw.as_string().map(|raw| parse(&raw)).transpose()
+    }
+
+    /// Run a session command in the background, logging failures.
+    pub fn spawn_command(cmd: &'static str, session: Option<String>) {
+        let args = Object::new();
+        if let Some(session) = session {
+            Reflect::set(&args, &"session".into(), &session.into()).ok();
+        }
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = invoke(cmd, args.into()).await {
+                log_error(Error::StoreFailToCreate(format!("{cmd}: {err:?}")));
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jwt(claims: &str) -> String {
+        format!("e30.{}.c2ln", URL_SAFE_NO_PAD.encode(claims))
+    }
+
+    #[test]
+    fn server_expiry_wins() {
+        let session = Session {
+            token: jwt(r#"{"exp":10}"#),
+            expires_at: Some(100),
+        };
+        assert!(session.check_at(99).is_ok());
+        assert!(matches!(session.check_at(100), Err(Error::ExpiredToken)));
+    }
+
+    #[test]
+    fn jwt_expiry_is_used_as_fallback() {
+        let session = Session {
+            token: jwt(r#"{"sub":"user","exp":50}"#),
+            expires_at: None,
+        };
+        assert_eq!(session.expiry().unwrap(), Some(50));
+        assert!(matches!(session.check_at(60), Err(Error::ExpiredToken)));
+    }
+
+    #[test]
+    fn malformed_tokens_are_rejected() {
+        let empty = Session {
+            token: String::new(),
+            expires_at: None,
+        };
+        assert!(matches!(empty.check_at(0), Err(Error::InvalidToken)));
+        let garbage = Session {
+            token: "a.!!!.c".to_string(),
+            expires_at: None,
+        };
+        assert!(matches!(garbage.check_at(0), Err(Error::InvalidToken)));
+        let not_json = Session {
+            token: jwt("not json"),
+            expires_at: None,
+        };
+        assert!(matches!(
+            not_json.check_at(0),
+            Err(Error::FailedToCheckToken)
+        ));
+        let opaque = Session {
+            token: "opaque".to_string(),
+            expires_at: None,
+        };
+        assert!(opaque.check_at(u64::MAX).is_ok());
+    }
+
+    #[test]
+    fn file_store_round_trip() {
+        let path = std::env::temp_dir()
+            .join(format!("ttyx-session-{}", std::process::id()))
+            .join("session.json");
+        let store = SessionStore::File(path.clone());
+        let session = Session {
+            token: "abc".to_string(),
+            expires_at: Some(1),
+        };
+        assert_eq!(store.load().unwrap(), None);
+        store.save(&session).unwrap();
+        assert_eq!(store.load().unwrap(), Some(session));
+        store.clear().unwrap();
+        assert_eq!(store.load().unwrap(), None);
+
+        std::fs::write(&path, "garbage").unwrap();
+        assert!(matches!(store.load(), Err(Error::TokenCouldNotBeRead)));
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,265 @@
+//! Snapshot tests of what the app draws, without a browser.
+//!
+//! [`Harness`] drives an [`App`] through the headless backend: keys, mouse events and
+//! actions go in, frames of a fixed size come out as text. [`assert_snapshot`] compares such
+//! a frame with `snapshots/<name>.txt` and fails with a line by line diff when they differ.
+//!
+//! Like in the browser, actions are only handled when a frame is drawn, so a key that
+//! sends one, e.g. `e` to start editing, needs a [`Step::Settle`] before the keys meant for
+//! what it opens.
+//!
+//! Effects advance a fixed time per frame, so a frame drawn while one is running is only
+//! stable across runs if the effect is. Use [`Step::Frames`] to let it finish first.
+//!
+//! Run the tests with `UPDATE_SNAPSHOTS=1` to write the current frames as the new snapshots,
+//! then review the changes with `git diff snapshots/`.
+//!
+//! [`key`], [`ctrl`] and [`shift`] build key events for the tests of single components.
+use std::fs;
+use std::path::PathBuf;
+
+use ratzilla::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use super::{buffer_lines, Action, Surface};
+use crate::app::App;
+
+/// Environment variable that turns comparing snapshots into writing them.
+pub const UPDATE_SNAPSHOTS: &str = "UPDATE_SNAPSHOTS";
+/// Frames drawn at most by [`Harness::settle`] before giving up on the actions running out.
+const MAX_SETTLE_FRAMES: usize = 20;
+
+/// One step of a script run by [`Harness::run`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    Key(KeyEvent),
+    Mouse(MouseEvent),
+    /// Sent through the action channel, like components do
+    Action(Action),
+    /// Draw a frame, which also handles the actions sent so far
+    Frame,
+    /// Draw frames until every action, and those it sent, has been handled
+    Settle,
+    /// Draw this many frames, e.g. to let an effect run its course
+    Frames(usize),
+}
+
+/// Key without modifiers, as the browser reports it.
+pub fn key(code: KeyCode) -> KeyEvent {
+    KeyEvent {
+        code,
+        ctrl: false,
+        alt: false,
+        shift: false,
+    }
+}
+
+/// Key with Ctrl held.
+pub fn ctrl(code: KeyCode) -> KeyEvent {
+    KeyEvent {
+        ctrl: true,
+        ..key(code)
+    }
+}
+
+/// Key with Shift held.
+pub fn shift(code: KeyCode) -> KeyEvent {
+    KeyEvent {
+        shift: true,
+        ..key(code)
+    }
+}
+
+impl Step {
+    /// Key without modifiers.
+    pub fn key(code: KeyCode) -> Self {
+        Step::Key(key(code))
+    }
+
+    /// One key per character of `text`.
+    pub fn text(text: &str) -> Vec<Self> {
+        text.chars().map(|c| Step::key(KeyCode::Char(c))).collect()
+    }
+
+    /// Left click on the cell at `x`, `y`.
+    pub fn click(x: u16, y: u16) -> Self {
+        Step::Mouse(MouseEvent {
+            button: MouseButton::Left,
+            event: MouseEventKind::Pressed,
+            x: x as u32,
+            y: y as u32,
+            ctrl: false,
+            alt: false,
+            shift: false,
+        })
+    }
+}
+
+/// App drawn into memory, wired up the way `main` does it.
+pub struct Harness {
+    pub app: App,
+    pub surface: Surface,
+    tx: UnboundedSender<Action>,
+    rx: UnboundedReceiver<Action>,
+}
+
+impl Harness {
+    /// Mounted app, drawn at `width` x `height` cells.
+    pub fn new(width: u16, height: u16) -> Self {
+        let (tx, rx) = unbounded_channel();
+        let mut app = App::new();
+        app.register_action_handler(tx.clone())
+            .expect("Failed to register the action handler");
+        app.mount().expect("Failed to mount the app");
+        Self {
+            app,
+            surface: Surface::headless(width, height).expect("Failed to create the surface"),
+            tx,
+            rx,
+        }
+    }
+
+    /// Apply the steps in order.
+    pub fn run(&mut self, steps: impl IntoIterator<Item = Step>) -> &mut Self {
+        for step in steps {
+            match step {
+                Step::Key(key) => self.app.handle_events(key),
+                Step::Mouse(mouse) => self.app.handle_mouse(mouse),
+                Step::Action(action) => self.send(action),
+                Step::Frame => {
+                    self.frame();
+                }
+                Step::Settle => {
+                    self.settle();
+                }
+                Step::Frames(count) => {
+                    for _ in 0..count {
+                        self.frame();
+                    }
+                }
+            }
+        }
+        self
+    }
+
+    pub fn send(&mut self, action: Action) {
+        self.tx.send(action).expect("The app dropped its receiver");
+    }
+
+    /// Draw a frame and return its text.
+    pub fn frame(&mut self) -> Vec<String> {
+        let Self {
+            app,
+            surface,
+            tx,
+            rx,
+        } = self;
+        surface
+            .draw(|frame| app.run(frame, rx, tx))
+            .expect("Failed to draw");
+        let backend = surface.terminal().backend().inner();
+        let lines = buffer_lines(backend.buffer().expect("Not a headless backend"));
+        app.between_frames(surface);
+        lines
+    }
+
+    /// Draw frames until no action is left to handle, returning the last one.
+    ///
+    /// Actions often send others, e.g. a toggle that opens an overlay, which are only
+    /// handled on the next frame.
+    pub fn settle(&mut self) -> Vec<String> {
+        let mut lines = self.frame();
+        for _ in 0..MAX_SETTLE_FRAMES {
+            if self.rx.is_empty() {
+                break;
+            }
+            lines = self.frame();
+        }
+        lines
+    }
+
+    /// Whether the settled frame shows `text` on one of its lines.
+    pub fn shows(&mut self, text: &str) -> bool {
+        self.settle().iter().any(|line| line.contains(text))
+    }
+
+    /// Compare the settled frame with the snapshot `name`.
+    pub fn assert_snapshot(&mut self, name: &str) {
+        let lines = self.settle();
+        assert_snapshot(name, &lines);
+    }
+}
+
+/// Path of the snapshot `name`.
+fn snapshot_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("snapshots")
+        .join(format!("{name}.txt"))
+}
+
+/// Compare `lines` with the snapshot `name`, or write them as the snapshot when
+/// `UPDATE_SNAPSHOTS` is set.
+///
+/// # Panics
+///
+/// When the snapshot is missing or differs, with a diff of the lines that changed.
+pub fn assert_snapshot(name: &str, lines: &[String]) {
+    let path = snapshot_path(name);
+    let actual = lines.join("\n") + "\n";
+    if std::env::var_os(UPDATE_SNAPSHOTS).is_some() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).expect("Failed to create the snapshot directory");
+        }
+        fs::write(&path, actual).expect("Failed to write the snapshot");
+        return;
+    }
+    let Ok(expected) = fs::read_to_string(&path) else {
+        panic!(
+            "Missing snapshot {}, run the tests with {UPDATE_SNAPSHOTS}=1 to create it",
+            path.display()
+        );
+    };
+    if expected != actual {
+        panic!(
+            "Snapshot {name} differs (- expected, + actual), run the tests with \
+             {UPDATE_SNAPSHOTS}=1 to accept the change:\n{}",
+            diff(&expected, &actual)
+        );
+    }
+}
+
+/// Line by line diff of two frames, with the number of every line that changed.
+///
+/// Frames have a fixed size, so lines are compared by position rather than aligned.
+fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    let mut out = String::new();
+    for i in 0..expected.len().max(actual.len()) {
+        let (old, new) = (expected.get(i), actual.get(i));
+        if old == new {
+            continue;
+        }
+        if let Some(old) = old {
+            out.push_str(&format!("{:>3} - |{old}|\n", i + 1));
+        }
+        if let Some(new) = new {
+            out.push_str(&format!("{:>3} + |{new}|\n", i + 1));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_lists_changed_lines() {
+        assert_eq!(diff("a\nb\nc\n", "a\nb\nc\n"), "");
+        assert_eq!(
+            diff("a\nb\nc\n", "a\nB\nc\nd\n"),
+            "  2 - |b|\n  2 + |B|\n  4 + |d|\n"
+        );
+    }
+}
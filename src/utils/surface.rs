@@ -0,0 +1,140 @@
+//! Terminal whose backend can be replaced while the app keeps running.
+//!
+//! Ratzilla's `draw_web` takes the terminal for good, so switching renderers used to mean
+//! reloading the page with another `?backend=`. [`Surface`] runs the animation frame loop
+//! itself and rebuilds the terminal between two frames instead.
+//!
+//! The same goes for recordings, the [`Recorder`] is installed into the terminal's
+//! [`RecordingBackend`] between two frames and taken out again when the recording stops.
+use std::cell::RefCell;
+use std::io;
+use std::rc::Rc;
+
+use ratzilla::ratatui::{Frame, Terminal};
+use wasm_bindgen::prelude::*;
+
+use super::{
+    browser_window, remember_backend_in_url, remove_backend_surfaces, BackendSelection,
+    BackendType, FpsTrackingBackend, MultiBackendBuilder, Recorder, RecordingBackend,
+};
+
+/// Terminal drawn in the browser, rebuilt with another backend on request.
+pub struct Surface {
+    terminal: Terminal<RecordingBackend<FpsTrackingBackend>>,
+    /// Makes the builder for every terminal, as backend options can't be reused
+    builder: Box<dyn Fn() -> MultiBackendBuilder>,
+}
+
+impl Surface {
+    /// Build the first terminal, with the backend from the URL or the builder's default.
+    pub fn new(builder: impl Fn() -> MultiBackendBuilder + 'static) -> io::Result<Self> {
+        let terminal = builder().build_terminal()?;
+        Ok(Self {
+            terminal,
+            builder: Box::new(builder),
+        })
+    }
+
+    /// Surface drawing into memory at `width` x `height` cells, for the snapshot tests and
+    /// native builds. Switching builds a headless terminal of the default size.
+    #[cfg(any(test, feature = "headless"))]
+    pub fn headless(width: u16, height: u16) -> io::Result<Self> {
+        Ok(Self {
+            terminal: super::headless_terminal(width, height)?,
+            builder: Box::new(|| MultiBackendBuilder::with_fallback(BackendType::Headless)),
+        })
+    }
+
+    pub fn terminal(&self) -> &Terminal<RecordingBackend<FpsTrackingBackend>> {
+        &self.terminal
+    }
+
+    /// How the current backend was picked.
+    pub fn selection(&self) -> &BackendSelection {
+        self.terminal.backend().inner().selection()
+    }
+
+    /// Draw one frame with `render`.
+    pub fn draw(&mut self, render: impl FnOnce(&mut Frame)) -> io::Result<()> {
+        self.terminal.draw(render).map(|_| ())
+    }
+
+    /// Record what is drawn from now on, starting with the next frame drawn in full.
+    pub fn start_recording(&mut self) -> io::Result<()> {
+        self.terminal.backend_mut().install(Recorder::new());
+        self.terminal.clear()
+    }
+
+    /// Stop recording, returning what was recorded if a recording was running.
+    pub fn stop_recording(&mut self) -> Option<Recorder> {
+        self.terminal.backend_mut().remove()
+    }
+
+    /// Tear down the current surface and draw with `backend_type` from the next frame on.
+    ///
+    /// Falls back like the first build does. If no backend of the chain works, the previous
+    /// backend is brought back and the returned selection says why the switch failed.
+    /// Errors only when even that fails, leaving nothing to draw on.
+    ///
+    /// A recording in progress goes on in the new terminal, from a full redraw.
+    pub fn switch(&mut self, backend_type: BackendType) -> io::Result<BackendSelection> {
+        let previous = self.selection().chosen;
+        let recorder = self.stop_recording();
+        remove_backend_surfaces();
+        let selection = match (self.builder)().backend(backend_type).build_terminal() {
+            Ok(terminal) => {
+                self.terminal = terminal;
+                self.selection().clone()
+            }
+            Err(err) => {
+                self.terminal = (self.builder)()
+                    .backend(previous)
+                    .fallback_chain([])
+                    .build_terminal()?;
+                BackendSelection {
+                    requested: backend_type,
+                    chosen: previous,
+                    failures: vec![(backend_type, err.to_string())],
+                }
+            }
+        };
+        if let Some(recorder) = recorder {
+            self.terminal.backend_mut().install(recorder);
+            self.terminal.clear()?;
+        }
+        // A reload keeps the backend
+        remember_backend_in_url(selection.chosen);
+        Ok(selection)
+    }
+
+    /// Draw with `render` on every animation frame, calling `between_frames` after each one.
+    ///
+    /// `between_frames` is where the backend can be switched, as no frame is being drawn.
+    pub fn draw_web<R, B>(self, mut render: R, mut between_frames: B)
+    where
+        R: FnMut(&mut Frame) + 'static,
+        B: FnMut(&mut Surface) + 'static,
+    {
+        let mut surface = self;
+        let callback = Rc::new(RefCell::new(None::<Closure<dyn FnMut()>>));
+        *callback.borrow_mut() = Some(Closure::new({
+            let callback = callback.clone();
+            move || {
+                if let Err(err) = surface.draw(&mut render) {
+                    log::error!("Failed to draw: {}", err);
+                }
+                between_frames(&mut surface);
+                request_animation_frame(callback.borrow().as_ref().unwrap());
+            }
+        }));
+        request_animation_frame(callback.borrow().as_ref().unwrap());
+    }
+}
+
+fn request_animation_frame(callback: &Closure<dyn FnMut()>) {
+    if let Some(window) = browser_window() {
+        window
+            .request_animation_frame(callback.as_ref().unchecked_ref())
+            .ok();
+    }
+}
@@ -0,0 +1,203 @@
+use std::cell::Cell;
+use std::future::Future;
+use std::rc::Rc;
+
+use futures_util::future::{abortable, AbortHandle};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::app::Page;
+use crate::utils::Action;
+
+/// Handle to a background task started with [`Tasks::spawn`].
+///
+/// Dropping the handle leaves the task running.
+#[derive(Debug, Clone)]
+pub struct TaskHandle {
+    abort: AbortHandle,
+    /// Set once the future has run to completion
+    done: Rc<Cell<bool>>,
+}
+
+impl TaskHandle {
+    /// Stop the task at its next await point, its result is never sent.
+    pub fn cancel(&self) {
+        self.abort.abort();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.abort.is_aborted()
+    }
+
+    /// Whether the task completed or was cancelled.
+    pub fn is_finished(&self) -> bool {
+        self.done.get() || self.is_cancelled()
+    }
+}
+
+/// Background work such as network requests, reporting back through the action channel.
+///
+/// In the browser tasks run on the JavaScript event loop through `spawn_local`. Natively
+/// they are queued on a tokio `LocalSet` that advances in [`Tasks::run_pending`], so the
+/// same futures, which don't have to be `Send`, work in both places.
+///
+/// A task can belong to a page, in which case it is cancelled when the page is left.
+pub struct Tasks {
+    // Action Handler
+    tx: Option<UnboundedSender<Action>>,
+    /// Tasks that may still be running, with the page they belong to
+    running: Vec<(Option<Page>, TaskHandle)>,
+    #[cfg(not(target_arch = "wasm32"))]
+    runtime: tokio::runtime::Runtime,
+    #[cfg(not(target_arch = "wasm32"))]
+    local: tokio::task::LocalSet,
+}
+
+impl Default for Tasks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tasks {
+    pub fn new() -> Self {
+        Self {
+            tx: None,
+            running: Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            runtime: tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("Failed to build the task runtime"),
+            #[cfg(not(target_arch = "wasm32"))]
+            local: tokio::task::LocalSet::new(),
+        }
+    }
+
+    pub fn register_action_handler(&mut self, tx: UnboundedSender<Action>) {
+        self.tx = Some(tx);
+    }
+
+    /// Run `future` in the background, sending the action it resolves to, if any.
+    ///
+    /// With an `owner` the task is cancelled by [`Tasks::cancel`] for that page.
+    pub fn spawn<F>(&mut self, owner: Option<Page>, future: F) -> TaskHandle
+    where
+        F: Future + 'static,
+        F::Output: Into<Option<Action>>,
+    {
+        self.running.retain(|(_, handle)| !handle.is_finished());
+
+        let (future, abort) = abortable(future);
+        let handle = TaskHandle {
+            abort,
+            done: Rc::new(Cell::new(false)),
+        };
+        let done = handle.done.clone();
+        let tx = self.tx.clone();
+        let task = async move {
+            // An aborted task stops here without reporting anything
+            if let Ok(output) = future.await {
+                done.set(true);
+                if let (Some(action), Some(tx)) = (output.into(), tx) {
+                    tx.send(action).ok();
+                }
+            }
+        };
+
+        #[cfg(target_arch = "wasm32")]
+        wasm_bindgen_futures::spawn_local(task);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.local.spawn_local(task);
+
+        self.running.push((owner, handle.clone()));
+        handle
+    }
+
+    /// Cancel the tasks belonging to `owner`, returning how many were still running.
+    pub fn cancel(&mut self, owner: Page) -> usize {
+        let mut cancelled = 0;
+        self.running.retain(|(task_owner, handle)| {
+            if handle.is_finished() {
+                return false;
+            }
+            if *task_owner != Some(owner) {
+                return true;
+            }
+            handle.cancel();
+            cancelled += 1;
+            false
+        });
+        if cancelled > 0 {
+            log::info!("Cancelled {} task(s) of the {} page", cancelled, owner);
+        }
+        cancelled
+    }
+
+    /// Give the tasks that are ready a chance to make progress.
+    ///
+    /// In the browser this does nothing, the event loop drives them on its own.
+    pub fn run_pending(&self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.runtime
+            .block_on(self.local.run_until(tokio::task::yield_now()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::{mpsc::unbounded_channel, oneshot};
+
+    /// Number of tasks still running.
+    fn running(tasks: &Tasks) -> usize {
+        tasks
+            .running
+            .iter()
+            .filter(|(_, handle)| !handle.is_finished())
+            .count()
+    }
+
+    #[test]
+    fn results_are_sent_as_actions() {
+        let (tx, mut rx) = unbounded_channel();
+        let mut tasks = Tasks::new();
+        tasks.register_action_handler(tx);
+        let handle = tasks.spawn(None, async { Action::LoggedIn });
+        tasks.spawn(None, async { None });
+        assert!(!handle.is_finished());
+
+        tasks.run_pending();
+        assert!(handle.is_finished());
+        assert_eq!(rx.try_recv(), Ok(Action::LoggedIn));
+        assert!(rx.try_recv().is_err());
+        assert_eq!(running(&tasks), 0);
+    }
+
+    #[test]
+    fn leaving_a_page_cancels_its_tasks() {
+        let (tx, mut rx) = unbounded_channel();
+        let mut tasks = Tasks::new();
+        tasks.register_action_handler(tx);
+        let (login_done, login_wait) = oneshot::channel::<()>();
+        let (help_done, help_wait) = oneshot::channel::<()>();
+        let login = tasks.spawn(Some(Page::Login), async move {
+            login_wait.await.ok();
+            Action::LoggedOut
+        });
+        tasks.spawn(Some(Page::Help), async move {
+            help_wait.await.ok();
+            Action::LoggedIn
+        });
+        tasks.run_pending();
+        assert_eq!(running(&tasks), 2);
+
+        assert_eq!(tasks.cancel(Page::Login), 1);
+        assert!(login.is_cancelled());
+        login_done.send(()).ok();
+        help_done.send(()).unwrap();
+        tasks.run_pending();
+        assert_eq!(rx.try_recv(), Ok(Action::LoggedIn));
+        assert!(rx.try_recv().is_err());
+        assert_eq!(tasks.cancel(Page::Login), 0);
+    }
+}
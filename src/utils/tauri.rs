@@ -0,0 +1,19 @@
+//! Bridge to the commands of the Tauri shell (`src-tauri`).
+use wasm_bindgen::prelude::*;
+use web_sys::js_sys::Reflect;
+
+use super::browser_window;
+
+#[wasm_bindgen]
+extern "C" {
+    /// Run the Tauri command `cmd` with `args`, an object of its named arguments.
+    #[wasm_bindgen(catch, js_namespace = ["window", "__TAURI__", "core"], js_name = invoke)]
+    pub async fn invoke(cmd: &str, args: JsValue) -> std::result::Result<JsValue, JsValue>;
+}
+
+/// Whether the page runs inside the Tauri shell with its global API enabled.
+pub fn is_available() -> bool {
+    browser_window()
+        .map(|window| Reflect::has(&window, &JsValue::from_str("__TAURI__")).unwrap_or(false))
+        .unwrap_or(false)
+}
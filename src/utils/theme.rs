@@ -0,0 +1,54 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use ratzilla::ratatui::style::{Color, Style};
+
+/// Colors the pages are drawn on.
+///
+/// Widgets with colors of their own keep them, everything else takes the theme's.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 2] = [Theme::Dark, Theme::Light];
+
+    /// Get the string representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Theme::Dark => "dark",
+            Theme::Light => "light",
+        }
+    }
+
+    /// Base style of every cell.
+    pub fn style(&self) -> Style {
+        match self {
+            Theme::Dark => Style::default().bg(Color::Black).fg(Color::White),
+            Theme::Light => Style::default().bg(Color::White).fg(Color::Black),
+        }
+    }
+}
+
+impl TryFrom<String> for Theme {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        match s.to_lowercase().as_str() {
+            "dark" => Ok(Theme::Dark),
+            "light" => Ok(Theme::Light),
+            _ => Err(format!(
+                "Invalid theme: '{s}'. Valid options are: dark, light"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
@@ -0,0 +1,70 @@
+//! Browser glue that ratzilla doesn't provide out of the box.
+use ratzilla::event::MouseEvent;
+use ratzilla::ratatui::layout::{Position, Rect};
+use tokio::sync::mpsc::UnboundedSender;
+use wasm_bindgen::prelude::*;
+use web_sys::Window;
+
+use super::Action;
+
+/// The browser window, or `None` outside the browser.
+///
+/// Calling into `web_sys` panics on native targets, so anything that may also run in native
+/// builds (tests, tooling) should go through this instead of `web_sys::window()`.
+pub fn browser_window() -> Option<Window> {
+    if cfg!(target_arch = "wasm32") {
+        web_sys::window()
+    } else {
+        None
+    }
+}
+
+/// Forward `paste` events from the document as `Action::Paste`.
+///
+/// Listening for the DOM event instead of reading the clipboard on Ctrl+V means pasting works
+/// without asking the user for clipboard permissions.
+pub fn on_paste_event(tx: UnboundedSender<Action>) {
+    let closure = Closure::<dyn FnMut(_)>::new(move |event: web_sys::ClipboardEvent| {
+        if let Some(text) = event
+            .clipboard_data()
+            .and_then(|data| data.get_data("text").ok())
+        {
+            event.prevent_default();
+            tx.send(Action::Paste(text)).ok();
+        }
+    });
+    if let Some(document) = browser_window().and_then(|w| w.document()) {
+        document
+            .add_event_listener_with_callback("paste", closure.as_ref().unchecked_ref())
+            .ok();
+    }
+    closure.forget();
+}
+
+/// Text selected in the document with the mouse, if any.
+pub fn selected_text() -> Option<String> {
+    let selection = browser_window()?.get_selection().ok()??;
+    let text = String::from(selection.to_string());
+    (!text.is_empty()).then_some(text)
+}
+
+/// Cell under the mouse pointer, for a terminal filling the window with `area`.
+///
+/// Ratzilla reports mouse positions in client pixels rather than cells. Outside the browser
+/// there is no window to measure, so the coordinates are taken to be cells already.
+pub fn cell_position(mouse: &MouseEvent, area: Rect) -> Position {
+    let size = browser_window().and_then(|window| {
+        let width = window.inner_width().ok()?.as_f64()?;
+        let height = window.inner_height().ok()?.as_f64()?;
+        (width > 0.0 && height > 0.0).then_some((width, height))
+    });
+    let Some((width, height)) = size else {
+        return Position::new(mouse.x as u16, mouse.y as u16);
+    };
+    let column = (mouse.x as f64 * area.width as f64 / width) as u16;
+    let row = (mouse.y as f64 * area.height as f64 / height) as u16;
+    Position::new(
+        area.x + column.min(area.width.saturating_sub(1)),
+        area.y + row.min(area.height.saturating_sub(1)),
+    )
+}